@@ -1,20 +1,28 @@
-use std::{fs, path::PathBuf, process::exit, str::FromStr};
+use std::{collections::BTreeMap, fs, io::Write, path::PathBuf, process::exit, str::FromStr};
 
-use clap::{CommandFactory, Parser, Subcommand};
+use chrono::Utc;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::{generate, Shell};
 use pants_gen::password::PasswordSpec;
+use secrecy::ExposeSecret;
 
 use enum_iterator::all;
+use inquire::validator::Validation;
 use pants_store::{
-    config::internal_config::BaseConfig,
-    errors::{ClientError, CommunicationError},
-    info::Info,
-    manager_message::ManagerMessage,
-    message::Message,
-    output::Output,
+    attachment::Attachment,
+    audit::AuditSettings,
+    client::PantsClient,
+    config::{internal_config::BaseConfig, template_config::TemplateConfig},
+    errors::{ClientError, CommunicationError, ManagerError},
+    file::BackupFile,
+    passphrase::PassphraseSpec,
+    reads::Reads,
+    rotation::EntryFilter,
     schema::Schema,
-    store::{Changes, SecretValue, Store, StoredValue},
-    vault::manager::VaultManager,
+    store::{Changes, FieldKind, SecretValue, Store, StoredValue},
+    strength::{Strength, StrengthPolicy},
+    template::Template,
+    url::{Site, UrlMatch},
     Password,
 };
 
@@ -25,6 +33,9 @@ use crate::{
     output::OutputStyle,
 };
 
+/// a field being added: its name, kind, value and the spec it was generated with
+type NewField = (String, FieldKind, SecretValue, Option<String>);
+
 #[derive(Parser)]
 pub struct CliArgs {
     #[command(subcommand)]
@@ -32,6 +43,14 @@ pub struct CliArgs {
     /// how to handle values pulled from vault
     #[arg(long, value_enum, default_value_t = OutputStyle::Clipboard)]
     output: OutputStyle,
+    /// vault to use when a command doesn't name one, overrides the configured default vault
+    #[arg(
+        long = "vault",
+        id = "default_vault",
+        global = true,
+        env = "PANTS_VAULT"
+    )]
+    default_vault: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -42,31 +61,44 @@ pub enum CLICommands {
     Add {
         /// the name of the entry to add
         key: String,
-        /// name of the vault
-        #[arg(default_value = "default")]
-        vault: String,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
         /// specify a password spec string to be used over the configured one
         #[arg(long)]
         spec: Option<String>,
+        /// prompt for the fields of a template from `templates.toml` (login, credit-card, ...)
+        #[arg(long)]
+        template: Option<String>,
+        /// tag the entry, can be given multiple times
+        #[arg(long)]
+        tag: Vec<String>,
+        /// put the entry in a folder, e.g. `infra/aws`
+        #[arg(long)]
+        folder: Option<String>,
     },
     /// lookup the given entry
     Get {
-        /// name of the entry
+        /// name of the entry, close matches across all vaults are offered when it doesn't exist
         key: String,
-        /// name of the vault
-        #[arg(default_value = "default")]
-        vault: String,
+        /// name of the vault to look in, defaults to every vault
+        vault: Option<String>,
+    },
+    /// show the current one-time code of the entry's TOTP fields
+    Otp {
+        /// name of the entry, close matches across all vaults are offered when it doesn't exist
+        key: String,
+        /// name of the vault to look in, defaults to every vault
+        vault: Option<String>,
     },
     /// update the entry
     Update {
         /// name of the entry
         key: String,
-        /// name of the vault
-        #[arg(default_value = "default")]
-        vault: String,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
         // #[command(subcommand)]
         // password: Option<Generate>,
-        /// specify a password spec string to be used over the configured one
+        /// specify a password spec string to be used over the entry's or the configured one
         #[arg(long)]
         spec: Option<String>,
     },
@@ -74,9 +106,8 @@ pub enum CLICommands {
     Delete {
         /// name of the entry
         key: Option<String>,
-        /// name of the vault
-        #[arg(default_value = "default")]
-        vault: String,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
     },
     /// rename an entry
     Rename {
@@ -84,24 +115,118 @@ pub enum CLICommands {
         from: String,
         /// new name of the entry
         to: String,
-        /// name of the vault
-        #[arg(default_value = "default")]
-        vault: String,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
+    },
+    /// move entries from one vault to another
+    Move {
+        /// name of the vault to move from
+        from: String,
+        /// name of the vault to move to
+        to: String,
+        /// names of the entries
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+    /// copy entries from one vault to another
+    Copy {
+        /// name of the vault to copy from
+        from: String,
+        /// name of the vault to copy to
+        to: String,
+        /// names of the entries
+        #[arg(required = true)]
+        keys: Vec<String>,
     },
     /// list the vaults/entries
     List {
         /// name of vault to list entries of
         vault: Option<String>,
+        /// only list entries with the tag, can be given multiple times
+        #[arg(long)]
+        tag: Vec<String>,
+        /// only list entries in the folder or its subfolders
+        #[arg(long)]
+        folder: Option<String>,
+    },
+    /// find entries by the values of their fields, secret fields are left out by default
+    Search {
+        /// name of the vault
+        vault: String,
+        /// text to look for, case is ignored
+        query: String,
+        /// also search password, TOTP seed and generic fields
+        #[arg(long)]
+        include_secrets: bool,
+    },
+    /// find the entries with a URL field for the site, by origin, host or domain
+    FindUrl {
+        /// URL or host of the site, like `https://accounts.google.com/login`
+        url: Site,
+        /// name of the vault, defaults to every vault
+        vault: Option<String>,
+    },
+    /// add or remove tags of an entry
+    Tag {
+        /// name of the entry
+        key: String,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
+        /// tags to add
+        #[arg(long)]
+        add: Vec<String>,
+        /// tags to remove
+        #[arg(long)]
+        remove: Vec<String>,
+    },
+    /// move an entry into a folder
+    Folder {
+        /// name of the entry
+        key: String,
+        /// path of the folder, e.g. `infra/aws`, `/` moves the entry out of any folder
+        path: String,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
     },
     /// interact with backups, defaults to creating a new backup
     Backup {
         /// name of the vault
         vault: String,
         /// how to interact with backups (nothing => make backup, list => list available backups,
-        /// restore => copy in a backup)
+        /// restore => copy in a backup, prune => remove old backups)
         #[command(subcommand)]
         option: Option<BackupCommand>,
     },
+    /// list entries past or near the maximum age set in `age_policies`
+    Due {
+        /// name of the vault, defaults to every vault
+        vault: Option<String>,
+    },
+    /// generate and save new values for the password fields of an entry
+    RotateEntry {
+        /// name of the entry
+        key: String,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
+        /// only regenerate this field, it doesn't have to be a password field
+        #[arg(long)]
+        field: Option<String>,
+        /// specify a password spec string to be used over the entry's or the configured one
+        #[arg(long)]
+        spec: Option<String>,
+    },
+    /// regenerate the password fields of many entries at once, printing a checklist of the
+    /// entries to update on their sites
+    RotateEntries {
+        /// name of the vault
+        vault: String,
+        /// entries to regenerate, a glob on the entry names or `tag:<tag>`
+        #[arg(long)]
+        filter: EntryFilter,
+        /// only regenerate this field, it doesn't have to be a password field
+        #[arg(long)]
+        field: Option<String>,
+    },
     /// rotate master password for the vault
     Rotate {
         /// name of the vault
@@ -109,17 +234,48 @@ pub enum CLICommands {
     },
     /// export the contents of the vault
     Export {
-        /// name of the vault
-        #[arg(default_value = "default")]
-        vault: String,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
     },
     /// import a file into a vault
     Import {
         /// path to the file
         path: PathBuf,
-        /// name of the vault
-        #[arg(default_value = "default")]
-        vault: String,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
+    },
+    /// manage vaults
+    Vault {
+        #[command(subcommand)]
+        command: VaultCommand,
+    },
+    /// manage files attached to entries
+    Attach {
+        #[command(subcommand)]
+        command: AttachCommand,
+    },
+    /// report reused, weak, off-spec and old passwords, without printing them
+    Audit {
+        /// name of the vault, defaults to every vault
+        vault: Option<String>,
+        /// spec the passwords should satisfy, defaults to the configured one
+        #[arg(long)]
+        spec: Option<String>,
+        /// days a password may go unchanged in vaults without an age policy
+        #[arg(long, default_value_t = 365)]
+        max_age: u32,
+        /// how to print the report
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        /// local copy of the Have I Been Pwned SHA-1 list, ordered by hash, to check against
+        #[arg(long)]
+        breached: Option<PathBuf>,
+    },
+    /// check the vaults for problems
+    Doctor {
+        /// offer to fix the problems found
+        #[arg(long)]
+        fix: bool,
     },
     /// generate password
     Gen(gen_args::CliArgs),
@@ -127,6 +283,12 @@ pub enum CLICommands {
     Completion { shell: Shell },
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
 #[derive(Subcommand)]
 pub enum EntryStyle {
     /// dealing with a password alone
@@ -147,23 +309,123 @@ pub enum BackupCommand {
     List,
     /// restore from existing backups
     Restore,
+    /// remove old backups
+    Prune {
+        /// number of most recent backups to keep
+        #[arg(long, default_value_t = 5)]
+        keep: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum VaultCommand {
+    /// rename a vault
+    Rename {
+        /// current name of the vault
+        from: String,
+        /// new name of the vault
+        to: String,
+    },
+    /// add an alternative name for a vault
+    Alias {
+        /// the alternative name
+        alias: String,
+        /// name of the vault
+        vault: String,
+    },
+    /// remove an alternative name for a vault
+    Unalias {
+        /// the alternative name
+        alias: String,
+    },
+    /// show or set the default vault
+    Default {
+        /// name of the vault to make the default
+        vault: Option<String>,
+    },
+    /// only show the tags, folders and public fields of a vault's entries after entering its
+    /// password
+    Hide {
+        /// name of the vault
+        vault: String,
+    },
+    /// show the tags and folders of a vault's entries without its password
+    Unhide {
+        /// name of the vault
+        vault: String,
+    },
+    /// show the usernames, URLs and emails of a vault's entries without its password again, for
+    /// `list`, `find-url` and the GUI
+    Publish {
+        /// name of the vault
+        vault: String,
+    },
+    /// only show the usernames, URLs and emails of a vault's entries after entering its password
+    Unpublish {
+        /// name of the vault
+        vault: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AttachCommand {
+    /// attach a file to an entry, replacing an attachment with the same name
+    Add {
+        /// name of the entry
+        key: String,
+        /// path to the file
+        path: PathBuf,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
+        /// name of the attachment, defaults to the file name
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// write an attachment to a file
+    Get {
+        /// name of the entry
+        key: String,
+        /// name of the attachment
+        name: String,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
+        /// file to write to, defaults to the attachment name, existing files are not overwritten
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// remove an attachment
+    Rm {
+        /// name of the entry
+        key: String,
+        /// name of the attachment
+        name: String,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
+    },
+    /// list the attachments of an entry
+    List {
+        /// name of the entry
+        key: String,
+        /// name of the vault, defaults to the configured default vault
+        vault: Option<String>,
+    },
 }
 
 pub struct CliApp {
     args: CliArgs,
     config: ClientConfig,
-    interface: VaultManager,
+    client: PantsClient,
 }
 
 impl CliApp {
     pub fn run() {
         let args = CliArgs::parse();
         let config = <ClientConfig as BaseConfig>::load_err();
-        let interface = VaultManager::default();
+        let client = PantsClient::default();
         let app = CliApp {
             args,
             config,
-            interface,
+            client,
         };
         app.execute()
     }
@@ -171,8 +433,18 @@ impl CliApp {
     pub fn execute(self) {
         match self.args.command {
             CLICommands::Gen(args) => {
-                if let Some(p) = args.execute() {
+                if let Some(spec) = args.passphrase(self.config.passphrase.clone()) {
+                    match spec.generate().and_then(|p| Ok((p, spec.strength()?))) {
+                        Ok((p, strength)) => {
+                            println!("{p}");
+                            eprintln!("{}", Self::describe_strength(&strength));
+                        }
+                        Err(e) => println!("{e}"),
+                    }
+                } else if let Some(p) = args.execute() {
                     println!("{p}");
+                    // on stderr so the password alone can still be piped
+                    eprintln!("{}", Self::describe_strength(&Strength::estimate(&p)));
                 } else {
                     println!("Could not satisfy password spec constraints");
                 }
@@ -186,7 +458,18 @@ impl CliApp {
                 );
             }
             ref command => {
-                match Self::process(&self.config, &self.args.output, self.interface, command) {
+                let default_vault = self
+                    .args
+                    .default_vault
+                    .clone()
+                    .unwrap_or_else(|| self.client.default_vault());
+                match Self::process(
+                    &self.config,
+                    &self.args.output,
+                    self.client,
+                    &default_vault,
+                    command,
+                ) {
                     Ok(()) => (),
                     Err(e) => {
                         println!("Error: {}", e);
@@ -199,215 +482,404 @@ impl CliApp {
     fn process(
         config: &ClientConfig,
         output_style: &OutputStyle,
-        mut manager: VaultManager,
+        mut client: PantsClient,
+        default_vault: &str,
         command: &CLICommands,
     ) -> anyhow::Result<()> {
-        let message = Self::construct_message(&mut manager, config, command)?;
-        let output = manager.receive(message)?;
-        Self::handle_output(config, output_style, output)
-    }
-    fn handle_output(
-        _config: &ClientConfig,
-        output_style: &OutputStyle,
-        output: Output,
-    ) -> anyhow::Result<()> {
-        match output {
-            Output::Nothing => Ok(()),
-            Output::Read(reads) => {
-                output_style.handle_reads(reads)?;
-                Ok(())
-            }
-            Output::List(items) => {
-                if items.is_empty() {
-                    println!("No entries");
-                } else {
-                    println!("Available entries:");
-                    for item in items {
-                        println!(" - {}", item);
-                    }
-                }
-                Ok(())
-            }
-            Output::Schema(schema) => {
-                println!("{}", schema);
-                Ok(())
-            }
-            Output::Info(data) => {
-                if data.data.is_empty() {
-                    println!("No vaults created yet");
-                } else {
-                    println!("{data}");
-                }
-                Ok(())
-            }
-            Output::Backup(backup) => {
-                println!("Backed up to: {}", backup);
-                Ok(())
-            }
-            Output::BackupFiles(backups) => {
-                for file in backups {
-                    println!("{}", file);
-                }
-                Ok(())
-            }
-            Output::Content(s) => {
-                println!("{s}");
-                Ok(())
-            }
-        }
-    }
-    fn construct_message(
-        manager: &mut VaultManager,
-        config: &ClientConfig,
-        command: &CLICommands,
-    ) -> anyhow::Result<ManagerMessage> {
+        let client = &mut client;
         match command {
-            CLICommands::New { name } => Ok(ManagerMessage::NewVault(name.into())),
+            CLICommands::New { name } => client.new_vault(name),
             CLICommands::Get { vault, key } => {
-                let password = Self::get_password("Vault password:")?;
-                Ok(ManagerMessage::VaultMessage(
-                    vault.to_string(),
-                    Message::Get(password, key.to_string()),
-                ))
+                let (vault, key) = Self::find_entry(client, default_vault, vault, key)?;
+                let password = Self::get_password(&format!("Password for {vault}:"))?;
+                let value = client.get(&vault, &key, password)?;
+                output_style.handle_reads(Self::entry(key, value))
+            }
+            CLICommands::Otp { vault, key } => {
+                let (vault, key) = Self::find_entry(client, default_vault, vault, key)?;
+                let password = Self::get_password(&format!("Password for {vault}:"))?;
+                let codes = client
+                    .totp(&vault, &key, password)?
+                    .into_iter()
+                    .map(|(field, code)| (format!("{key}-{field}"), code))
+                    .collect();
+                output_style.handle_otp(codes)
             }
             CLICommands::Update { vault, key, spec } => {
-                let schema = Self::get_schema(manager, vault.into())?;
-                match schema.get(key) {
-                    None => Err(CommunicationError::NoEntry.into()),
-                    Some(fields) => {
-                        let spec = PasswordSpec::from_str(
-                            &spec.clone().unwrap_or_else(|| config.password_spec.clone()),
-                        )?;
-                        let changes = Self::prompt_update(fields, &spec)?;
-                        if changes.unchanged(fields) {
-                            return Err(ClientError::NoChanges.into());
-                        }
-                        let password = Self::get_password("Vault password:")?;
-                        Ok(ManagerMessage::VaultMessage(
-                            vault.into(),
-                            Message::Change(password, key.to_string(), changes),
-                        ))
-                    }
+                let vault = &Self::vault_name(client, default_vault, vault);
+                let schema = client.schema(vault)?;
+                let fields = schema.get(key).ok_or(CommunicationError::NoEntry)?;
+                let spec = spec.as_deref().map(PasswordSpec::from_str).transpose()?;
+                let default = PasswordSpec::from_str(&config.password_spec)?;
+                let changes =
+                    Self::prompt_update(&schema, key, spec.as_ref(), &default, &config.passphrase)?;
+                if changes.unchanged(fields) {
+                    return Err(ClientError::NoChanges.into());
                 }
+                let password = Self::get_password("Vault password:")?;
+                let value = client.change(vault, key, password, changes)?;
+                output_style.handle_reads(Self::entry(key.into(), value))
             }
             CLICommands::Rename { from, to, vault } => {
-                let schema = Self::get_schema(manager, vault.into())?;
-                let orig = schema.get(from);
-                let new = schema.get(to);
-                match (orig, new) {
+                let vault = &Self::vault_name(client, default_vault, vault);
+                let schema = client.schema(vault)?;
+                match (schema.get(from), schema.get(to)) {
                     (Some(_), None) => {
                         let password = Self::get_password("Vault password:")?;
-                        Ok(ManagerMessage::VaultMessage(
-                            vault.into(),
-                            Message::Rename(password, from.into(), to.into()),
-                        ))
+                        client.rename(vault, from, to, password)
                     }
                     _ => Err(ClientError::CantRename.into()),
                 }
             }
             CLICommands::Delete { vault, key } => {
+                let vault = &Self::vault_name(client, default_vault, vault);
                 if let Some(key) = key {
                     let password = Self::get_password("Vault password:")?;
-                    Ok(ManagerMessage::VaultMessage(
-                        vault.into(),
-                        Message::Delete(password, key.to_string()),
-                    ))
+                    client.delete(vault, key, password)
                 } else {
                     let choice =
                         inquire::Confirm::new("Are you sure you want to delete the whole vault?")
                             .prompt();
-                    let schema = Self::get_schema(manager, vault.into())?;
+                    let schema = client.schema(vault)?;
                     if schema.is_empty() {
-                        Ok(ManagerMessage::DeleteEmptyVault(vault.into()))
+                        client.delete_empty_vault(vault)
                     } else {
                         let password = Self::get_password("Vault password:")?;
                         match choice {
-                            Ok(true) => Ok(ManagerMessage::DeleteVault(vault.into(), password)),
-                            _ => Ok(ManagerMessage::Empty),
+                            Ok(true) => client.delete_vault(vault, password),
+                            _ => Ok(()),
                         }
                     }
                 }
             }
-            CLICommands::Add { key, vault, spec } => {
-                let schema = Self::get_schema(manager, vault.into())?;
-                match schema.get(key) {
-                    None => {
-                        let spec = PasswordSpec::from_str(
-                            &spec.clone().unwrap_or(config.password_spec.clone()),
-                        )?;
-
-                        let password = Self::password_prompt_add(manager, vault)?;
-                        let store = Self::prompt_add(&spec)?;
-                        Ok(ManagerMessage::VaultMessage(
-                            vault.into(),
-                            Message::Update(password, key.into(), store),
-                        ))
+            CLICommands::Move { from, to, keys } => {
+                let (from_password, to_password) =
+                    Self::password_prompt_transfer(client, from, to, keys)?;
+                let backups =
+                    client.move_entries(from, from_password, to, to_password, keys.clone())?;
+                Self::print_backups(backups);
+                Ok(())
+            }
+            CLICommands::Copy { from, to, keys } => {
+                let (from_password, to_password) =
+                    Self::password_prompt_transfer(client, from, to, keys)?;
+                let backups =
+                    client.copy_entries(from, from_password, to, to_password, keys.clone())?;
+                Self::print_backups(backups);
+                Ok(())
+            }
+            CLICommands::Add {
+                key,
+                vault,
+                spec,
+                template,
+                tag,
+                folder,
+            } => {
+                let template = match template {
+                    Some(name) => Some(
+                        <TemplateConfig as BaseConfig>::load()?
+                            .get(name)
+                            .cloned()
+                            .ok_or_else(|| ClientError::NoTemplate(name.into()))?,
+                    ),
+                    None => None,
+                };
+                let vault = &Self::vault_name(client, default_vault, vault);
+                let schema = client.schema(vault)?;
+                if schema.get(key).is_some() {
+                    return Err(CommunicationError::ExistingEntry.into());
+                }
+                let spec =
+                    PasswordSpec::from_str(&spec.clone().unwrap_or(config.password_spec.clone()))?;
+
+                let password = Self::password_prompt_add(client, vault)?;
+                let mut store = match template {
+                    Some(template) => Self::prompt_template(&template, &spec, schema.strength)?,
+                    None => Self::prompt_add(&spec, &config.passphrase, schema.strength)?,
+                };
+                store.tags.extend(tag.iter().cloned());
+                if let Some(folder) = folder {
+                    store.set_folder(folder);
+                }
+                client.update(vault, key, password, store.clone())?;
+                output_style.handle_reads(Self::entry(key.into(), store))
+            }
+            CLICommands::Due { vault } => {
+                let info = client.info()?;
+                let vaults = match vault {
+                    Some(vault) => {
+                        let vault = client.resolve(vault);
+                        let schema = info
+                            .get(&vault)
+                            .cloned()
+                            .ok_or(ManagerError::VaultDoesNotExist)?;
+                        vec![(vault, schema)]
+                    }
+                    None => info.into_iter().collect(),
+                };
+                let now = Utc::now();
+                let mut any = false;
+                for (vault, mut schema) in vaults {
+                    if schema.policy.is_empty() {
+                        continue;
+                    }
+                    if schema.hidden {
+                        let password = Self::get_password(&format!("Password for {vault}:"))?;
+                        schema = client.metadata(&vault, password)?;
+                    }
+                    let due = schema.due(now);
+                    if due.is_empty() {
+                        continue;
+                    }
+                    any = true;
+                    println!("{vault}:");
+                    for (key, status) in due {
+                        println!(" {key}: {status}");
+                    }
+                }
+                if !any {
+                    println!("Nothing is due for rotation");
+                }
+                Ok(())
+            }
+            CLICommands::RotateEntry {
+                key,
+                vault,
+                field,
+                spec,
+            } => {
+                let vault = &Self::vault_name(client, default_vault, vault);
+                let schema = client.schema(vault)?;
+                let fields = schema.get(key).ok_or(CommunicationError::NoEntry)?;
+                let targets: Vec<&String> = match field {
+                    Some(field) => vec![fields
+                        .iter()
+                        .find(|f| *f == field)
+                        .ok_or_else(|| ClientError::NoField(field.into()))?],
+                    None => fields
+                        .iter()
+                        .filter(|f| schema.kind(key, f) == FieldKind::Password)
+                        .collect(),
+                };
+                if targets.is_empty() {
+                    return Err(ClientError::NoPasswordField.into());
+                }
+                let spec = spec.as_deref().map(PasswordSpec::from_str).transpose()?;
+                let default = PasswordSpec::from_str(&config.password_spec)?;
+                let mut changes = Changes::new(fields);
+                for target in targets {
+                    let spec = Self::field_spec(spec.as_ref(), schema.spec(key, target), &default)?;
+                    let value = spec.generate().ok_or(ClientError::BadPasswordSpec)?;
+                    changes.insert(target, StoredValue::new(value).into());
+                    changes.set_spec(target, Some(spec.to_string()));
+                }
+                let password = Self::get_password("Vault password:")?;
+                let value = client.change(vault, key, password, changes)?;
+                output_style.handle_reads(Self::entry(key.into(), value))
+            }
+            CLICommands::RotateEntries {
+                vault,
+                filter,
+                field,
+            } => {
+                let schema = client.schema(vault)?;
+                let prompt = if schema.hidden && matches!(filter, EntryFilter::Tag(_)) {
+                    format!("Regenerate the entries of {vault} matching `{filter}`?")
+                } else {
+                    let keys: Vec<&String> = schema
+                        .data
+                        .keys()
+                        .filter(|key| filter.matches(key, &schema.tags(key)))
+                        .filter(|key| {
+                            schema.data[*key].iter().any(|f| match field {
+                                Some(field) => f == field,
+                                None => schema.kind(key, f) == FieldKind::Password,
+                            })
+                        })
+                        .collect();
+                    if keys.is_empty() {
+                        return Err(ClientError::NoMatches.into());
+                    }
+                    for key in &keys {
+                        println!(" - {key}");
                     }
-                    Some(_) => Err(CommunicationError::ExistingEntry.into()),
+                    format!("Regenerate these {} entries?", keys.len())
+                };
+                if !inquire::Confirm::new(&prompt)
+                    .with_default(false)
+                    .prompt()?
+                {
+                    return Ok(());
                 }
+                let default = PasswordSpec::from_str(&config.password_spec)?;
+                let password = Self::get_password("Vault password:")?;
+                let rotation = client.rotate_entries(
+                    vault,
+                    password,
+                    filter.clone(),
+                    field.clone(),
+                    default,
+                )?;
+                print!("{rotation}");
+                Ok(())
             }
             CLICommands::Rotate { vault } => {
                 let password = Self::get_password("Vault password:")?;
                 let new_password = Self::get_password_confirm("New vault password:")?;
-                Ok(ManagerMessage::VaultMessage(
-                    vault.into(),
-                    Message::Rotate(password, new_password),
-                ))
+                let backup = client.rotate(vault, password, new_password)?;
+                println!("Backed up to: {}", backup);
+                Ok(())
             }
             CLICommands::Backup { vault, option } => match option {
                 None => {
                     let password = Self::get_password("Vault password:")?;
-                    Ok(ManagerMessage::VaultMessage(
-                        vault.into(),
-                        Message::Backup(password),
-                    ))
-                }
-                Some(BackupCommand::List) => Ok(ManagerMessage::VaultMessage(
-                    vault.into(),
-                    Message::BackupList,
-                )),
+                    let backup = client.backup(vault, password)?;
+                    println!("Backed up to: {}", backup);
+                    Ok(())
+                }
+                Some(BackupCommand::List) => {
+                    Self::print_backups(client.backups(vault)?);
+                    Ok(())
+                }
+                Some(BackupCommand::Prune { keep }) => {
+                    Self::print_backups(client.prune_backups(vault, *keep)?);
+                    Ok(())
+                }
                 Some(BackupCommand::Restore) => {
-                    match manager.receive(ManagerMessage::VaultMessage(
-                        vault.into(),
-                        Message::BackupList,
-                    ))? {
-                        Output::BackupFiles(files) => {
-                            let backup_file = inquire::Select::new("Restore from:", files)
-                                .with_help_message("Choose the backup file to restore from")
-                                .prompt()?;
-                            let password = Self::get_password("Current password")?;
-                            let backup_password = Self::get_password("Backup's password:")?;
-                            Ok(ManagerMessage::VaultMessage(
-                                vault.into(),
-                                Message::Restore(password, backup_password, backup_file),
-                            ))
+                    let files = client.backups(vault)?;
+                    let backup_file = inquire::Select::new("Restore from:", files)
+                        .with_help_message("Choose the backup file to restore from")
+                        .prompt()?;
+                    let password = Self::get_password("Current password")?;
+                    let backup_password = Self::get_password("Backup's password:")?;
+                    let backup = client.restore(vault, password, backup_password, backup_file)?;
+                    println!("Backed up to: {}", backup);
+                    Ok(())
+                }
+            },
+            CLICommands::List { vault, tag, folder } => {
+                let filtering = !tag.is_empty() || folder.is_some();
+                match vault {
+                    Some(name) => {
+                        let mut schema = client.schema(name)?;
+                        if filtering && schema.hidden {
+                            let password = Self::get_password("Vault password:")?;
+                            schema = client.metadata(name, password)?;
+                        }
+                        if !filtering {
+                            println!("{schema}");
+                            return Ok(());
+                        }
+                        let filtered = schema.filter(tag, folder.as_deref());
+                        if filtered.is_empty() {
+                            println!("No matching entries");
+                        } else {
+                            println!("{filtered}");
+                        }
+                    }
+                    None => {
+                        let info = client.info()?;
+                        if !filtering {
+                            if info.data.is_empty() {
+                                println!("No vaults created yet");
+                            } else {
+                                println!("{info}");
+                            }
+                            return Ok(());
+                        }
+                        let filtered = info.filter(tag, folder.as_deref());
+                        if filtered.data.is_empty() {
+                            println!("No matching entries");
+                        } else {
+                            println!("{filtered}");
+                        }
+                        for vault in info.hidden() {
+                            println!(
+                                "Tags and folders of {vault} are hidden, list it by name to search it"
+                            );
                         }
-                        _ => Err(Box::new(CommunicationError::UnexpectedOutput).into()),
                     }
                 }
-            },
-            // CLICommands::List => Ok(Message::Schema),
-            CLICommands::List { vault } => {
-                if let Some(name) = vault {
-                    Ok(ManagerMessage::VaultMessage(name.into(), Message::Schema))
+                Ok(())
+            }
+            CLICommands::FindUrl { url, vault } => {
+                match vault {
+                    Some(name) => {
+                        let password = if client.schema(name)?.unpublished {
+                            Some(Self::get_password("Vault password:")?)
+                        } else {
+                            None
+                        };
+                        Self::print_url_matches(client.find_url_in(name, url, password)?, url);
+                    }
+                    None => {
+                        let info = client.info()?;
+                        Self::print_url_matches(info.find_url(url), url);
+                        for vault in info.unpublished() {
+                            println!(
+                                "URLs of {vault} aren't public, give it as the vault to search it"
+                            );
+                        }
+                    }
+                }
+                Ok(())
+            }
+            CLICommands::Search {
+                vault,
+                query,
+                include_secrets,
+            } => {
+                let vault = client.resolve(vault);
+                let password = Self::get_password("Vault password:")?;
+                let schema = client.search(&vault, password, query, *include_secrets)?;
+                if schema.is_empty() {
+                    println!("No matching entries");
                 } else {
-                    Ok(ManagerMessage::Info)
+                    println!("{schema}");
                 }
+                Ok(())
+            }
+            CLICommands::Tag {
+                key,
+                vault,
+                add,
+                remove,
+            } => {
+                let vault = &Self::vault_name(client, default_vault, vault);
+                let schema = client.schema(vault)?;
+                if schema.get(key).is_none() {
+                    return Err(CommunicationError::NoEntry.into());
+                }
+                if add.is_empty() && remove.is_empty() {
+                    return Err(ClientError::NoChanges.into());
+                }
+                let password = Self::get_password("Vault password:")?;
+                client.tag(vault, key, password, add.clone(), remove.clone())
+            }
+            CLICommands::Folder { key, path, vault } => {
+                let vault = &Self::vault_name(client, default_vault, vault);
+                let schema = client.schema(vault)?;
+                if schema.get(key).is_none() {
+                    return Err(CommunicationError::NoEntry.into());
+                }
+                let password = Self::get_password("Vault password:")?;
+                client.folder(vault, key, password, path)
             }
             CLICommands::Export { vault } => {
+                let vault = &Self::vault_name(client, default_vault, vault);
                 let password = Self::get_password("Vault password:")?;
-                Ok(ManagerMessage::VaultMessage(
-                    vault.into(),
-                    Message::Export(password),
-                ))
+                println!("{}", client.export(vault, password)?);
+                Ok(())
             }
             CLICommands::Import { vault, path } => {
-                let info = Self::get_info(manager)?;
+                let vault = &Self::vault_name(client, default_vault, vault);
+                let info = client.info()?;
                 let schema = info.get(vault).cloned().unwrap_or(Schema::default());
                 let new_vault = !info.data.contains_key(vault);
                 let confirm_password = new_vault || schema.is_empty();
                 if new_vault {
-                    manager.receive(ManagerMessage::NewVault(vault.into()))?;
+                    client.new_vault(vault)?;
                 }
                 let content = fs::read_to_string(path)?;
                 let data = serde_json::from_str(&content)?;
@@ -416,10 +888,140 @@ impl CliApp {
                 } else {
                     Self::get_password("Vault password:")?
                 };
-                Ok(ManagerMessage::VaultMessage(
-                    vault.into(),
-                    Message::Import(password, data),
-                ))
+                client.import(vault, password, data)
+            }
+            CLICommands::Vault { command } => match command {
+                VaultCommand::Rename { from, to } => client.rename_vault(from, to),
+                VaultCommand::Alias { alias, vault } => client.add_alias(alias, vault),
+                VaultCommand::Unalias { alias } => client.remove_alias(alias),
+                VaultCommand::Default { vault } => match vault {
+                    Some(vault) => client.set_default_vault(vault),
+                    None => {
+                        println!("{}", client.default_vault());
+                        Ok(())
+                    }
+                },
+                VaultCommand::Hide { vault } => client.hide_metadata(vault, true),
+                VaultCommand::Unhide { vault } => {
+                    println!("Metadata is shown again the next time the vault is saved");
+                    client.hide_metadata(vault, false)
+                }
+                VaultCommand::Publish { vault } => {
+                    println!("Public fields are shown the next time the vault is saved");
+                    client.publish_fields(vault, true)
+                }
+                VaultCommand::Unpublish { vault } => client.publish_fields(vault, false),
+            },
+            CLICommands::Attach { command } => match command {
+                AttachCommand::Add {
+                    key,
+                    path,
+                    vault,
+                    name,
+                } => {
+                    let vault = Self::vault_name(client, default_vault, vault);
+                    let name = match name {
+                        Some(name) => name.clone(),
+                        None => path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().to_string())
+                            .ok_or(ClientError::NoFileName)?,
+                    };
+                    let attachment = Attachment::new(fs::read(path)?);
+                    let password = Self::get_password("Vault password:")?;
+                    client.attach(&vault, key, &name, password, attachment)
+                }
+                AttachCommand::Get {
+                    key,
+                    name,
+                    vault,
+                    out,
+                } => {
+                    let vault = Self::vault_name(client, default_vault, vault);
+                    let password = Self::get_password("Vault password:")?;
+                    let attachment = client.attachment(&vault, key, name, password)?;
+                    let path = out.clone().unwrap_or_else(|| name.into());
+                    let mut options = fs::OpenOptions::new();
+                    options.write(true).create_new(true);
+                    #[cfg(unix)]
+                    {
+                        use std::os::unix::fs::OpenOptionsExt;
+                        options.mode(0o600);
+                    }
+                    options.open(&path)?.write_all(attachment.expose_secret())?;
+                    println!("Wrote {:?}", path);
+                    Ok(())
+                }
+                AttachCommand::Rm { key, name, vault } => {
+                    let vault = Self::vault_name(client, default_vault, vault);
+                    let password = Self::get_password("Vault password:")?;
+                    client.detach(&vault, key, name, password)
+                }
+                AttachCommand::List { key, vault } => {
+                    let vault = Self::vault_name(client, default_vault, vault);
+                    let password = Self::get_password("Vault password:")?;
+                    let attachments = client.attachments(&vault, key, password)?;
+                    if attachments.is_empty() {
+                        println!("No attachments");
+                    } else {
+                        for (name, info) in attachments {
+                            println!(" - {name} ({info})");
+                        }
+                    }
+                    Ok(())
+                }
+            },
+            CLICommands::Audit {
+                vault,
+                spec,
+                max_age,
+                breached,
+                format,
+            } => {
+                let info = client.info()?;
+                let vaults = match vault {
+                    Some(vault) => vec![client.resolve(vault)],
+                    None => info
+                        .data
+                        .iter()
+                        .filter(|(_, schema)| !schema.is_empty())
+                        .map(|(name, _)| name.clone())
+                        .collect(),
+                };
+                let mut passwords = vec![];
+                for vault in vaults {
+                    let password = Self::get_password(&format!("Password for {vault}:"))?;
+                    passwords.push((vault, password));
+                }
+                let spec = PasswordSpec::from_str(
+                    &spec.clone().unwrap_or_else(|| config.password_spec.clone()),
+                )?;
+                let settings = AuditSettings {
+                    spec: Some(spec),
+                    max_age: *max_age,
+                    breached: breached.clone(),
+                };
+                let audit = client.audit(passwords, settings)?;
+                match format {
+                    ReportFormat::Text => print!("{audit}"),
+                    ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&audit)?),
+                }
+                Ok(())
+            }
+            CLICommands::Doctor { fix } => {
+                if *fix {
+                    Self::prompt_repair(client)?;
+                }
+                let problems = client.doctor()?;
+                if problems.is_empty() {
+                    println!("No problems found");
+                } else {
+                    for problem in problems {
+                        println!("{problem}");
+                        println!("  fix: {}", problem.fix());
+                    }
+                }
+                Ok(())
             }
             CLICommands::Gen(_) | CLICommands::Completion { .. } => {
                 panic!("Should have branched before this")
@@ -427,15 +1029,28 @@ impl CliApp {
         }
     }
 
+    /// a single entry in the shape the output style prints
+    fn entry(key: String, value: Store) -> Reads<Store> {
+        let mut reads = Reads::new();
+        reads.insert(key, value);
+        reads
+    }
+
+    fn print_backups(backups: Vec<BackupFile>) {
+        for file in backups {
+            println!("{}", file);
+        }
+    }
+
     /// prompt for a password and handle the case that the user needs to create
     /// a new vault
-    fn password_prompt_add(manager: &mut VaultManager, vault: &str) -> anyhow::Result<Password> {
-        let info = Self::get_info(manager)?;
+    fn password_prompt_add(client: &mut PantsClient, vault: &str) -> anyhow::Result<Password> {
+        let info = client.info()?;
         let schema = info.get(vault).cloned().unwrap_or(Schema::default());
         let new_vault = !info.data.contains_key(vault);
         let confirm_password = new_vault || schema.is_empty();
         if new_vault {
-            manager.receive(ManagerMessage::NewVault(vault.into()))?;
+            client.new_vault(vault)?;
         }
         if confirm_password {
             let ans =
@@ -451,19 +1066,148 @@ impl CliApp {
         }
     }
 
+    /// go through the problems found by the doctor and apply the fixes the user agrees to
+    fn prompt_repair(client: &mut PantsClient) -> anyhow::Result<()> {
+        for problem in client.doctor()? {
+            let ans = inquire::Confirm::new(&format!("{problem}\n  {}?", problem.fix()))
+                .with_default(false)
+                .prompt()?;
+            if !ans {
+                continue;
+            }
+            let password = if problem.needs_password() {
+                Some(Self::get_password("Vault password:")?)
+            } else {
+                None
+            };
+            if let Err(e) = client.repair(problem, password) {
+                println!("Error: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// the vault and name of the entry `query` refers to, an exact match in the named vault or
+    /// the default one wins, otherwise the closest matches are searched for and picked from
+    fn find_entry(
+        client: &mut PantsClient,
+        default_vault: &str,
+        vault: &Option<String>,
+        query: &str,
+    ) -> anyhow::Result<(String, String)> {
+        let mut info = client.info()?;
+        let preferred = Self::vault_name(client, default_vault, vault);
+        if info
+            .get(&preferred)
+            .is_some_and(|schema| schema.get(query).is_some())
+        {
+            return Ok((preferred, query.into()));
+        }
+        if vault.is_some() {
+            let schema = info
+                .data
+                .remove(&preferred)
+                .ok_or(ManagerError::VaultDoesNotExist)?;
+            info = BTreeMap::from([(preferred, schema)]).into();
+        }
+        let mut matches = info.find(query);
+        let exact = matches.iter().filter(|m| m.is_exact()).count();
+        if exact > 0 {
+            matches.truncate(exact);
+        }
+        let found = match matches.len() {
+            0 => return Err(CommunicationError::NoEntry.into()),
+            1 => {
+                let found = matches.remove(0);
+                if !found.is_exact() {
+                    println!("Using {found}");
+                }
+                found
+            }
+            _ => inquire::Select::new("Which entry:", matches)
+                .with_help_message("Closest matches first")
+                .prompt()?,
+        };
+        Ok((found.vault, found.key))
+    }
+
+    /// the vault named on the command line or the default one, with aliases resolved
+    fn vault_name(client: &PantsClient, default_vault: &str, vault: &Option<String>) -> String {
+        client.resolve(vault.as_deref().unwrap_or(default_vault))
+    }
+
+    /// check the entries can be moved between the vaults and prompt for both passwords
+    fn password_prompt_transfer(
+        client: &mut PantsClient,
+        from: &str,
+        to: &str,
+        keys: &[String],
+    ) -> anyhow::Result<(Password, Password)> {
+        let from = &client.resolve(from);
+        let to = &client.resolve(to);
+        let info = client.info()?;
+        let source = info.get(from).ok_or(ManagerError::VaultDoesNotExist)?;
+        let destination = info.get(to).cloned().unwrap_or_default();
+        for key in keys {
+            if source.get(key).is_none() {
+                return Err(CommunicationError::NoEntry.into());
+            }
+            if destination.get(key).is_some() {
+                return Err(CommunicationError::ExistingEntry.into());
+            }
+        }
+        let from_password = Self::get_password(&format!("Password for {from}:"))?;
+        let to_password = Self::password_prompt_add(client, to)?;
+        Ok((from_password, to_password))
+    }
+
     /// Prompt for a new entry into a vault
-    fn prompt_add(spec: &PasswordSpec) -> anyhow::Result<Store> {
+    fn prompt_add(
+        spec: &PasswordSpec,
+        passphrase: &PassphraseSpec,
+        strength: StrengthPolicy,
+    ) -> anyhow::Result<Store> {
         let mut store = Store::default();
         loop {
-            match Self::prompt_new_entry(spec)? {
+            match Self::prompt_new_entry(spec, passphrase, strength)? {
                 None => break,
-                Some((k, v)) => store.insert(&k, v),
+                Some((k, kind, v, spec)) => {
+                    store.insert_kind(&k, kind, v);
+                    store.set_spec(&k, spec);
+                }
             }
         }
         Ok(store)
     }
 
-    fn prompt_new_entry(spec: &PasswordSpec) -> anyhow::Result<Option<(String, SecretValue)>> {
+    /// Prompt for the fields of a template, generating the ones it asks for
+    fn prompt_template(
+        template: &Template,
+        spec: &PasswordSpec,
+        strength: StrengthPolicy,
+    ) -> anyhow::Result<Store> {
+        let mut store = Store::default();
+        for field in &template.fields {
+            let value = if field.generate {
+                let spec = match &field.spec {
+                    Some(spec) => PasswordSpec::from_str(spec)?,
+                    None => spec.clone(),
+                };
+                store.set_spec(&field.name, Some(spec.to_string()));
+                spec.generate().ok_or(ClientError::BadPasswordSpec)?
+            } else {
+                Self::prompt_value(&format!("{}:", field.name), field.kind, strength)?
+            };
+            store.insert_kind(&field.name, field.kind, StoredValue::new(value).into());
+        }
+        Ok(store)
+    }
+
+    fn prompt_new_entry(
+        spec: &PasswordSpec,
+        passphrase: &PassphraseSpec,
+        strength: StrengthPolicy,
+    ) -> anyhow::Result<Option<NewField>> {
         let choice = inquire::Select::new("Type of entry", all::<NewEntry>().collect()).prompt()?;
         match choice {
             NewEntry::Done => Ok(None),
@@ -471,23 +1215,145 @@ impl CliApp {
                 let ident_input = inquire::Text::new("Name of field:")
                     .with_help_message("The type of the field (username, password, etc)")
                     .prompt()?;
+                let kind = Self::prompt_kind(FieldKind::infer(&ident_input))?;
                 let value = spec.generate().ok_or(ClientError::BadPasswordSpec)?;
-                Ok(Some((ident_input, StoredValue::new(value).into())))
+                Ok(Some((
+                    ident_input,
+                    kind,
+                    StoredValue::new(value).into(),
+                    Some(spec.to_string()),
+                )))
             }
-            NewEntry::Manual => {
+            NewEntry::Passphrase => {
                 let ident_input = inquire::Text::new("Name of field:")
                     .with_help_message("The type of the field (username, password, etc)")
                     .prompt()?;
-                let value_input = inquire::Text::new("Value for field:")
-                    .with_help_message("The actual value to be stored.")
+                let kind = Self::prompt_kind(FieldKind::infer(&ident_input))?;
+                let value = passphrase.generate()?;
+                Ok(Some((
+                    ident_input,
+                    kind,
+                    StoredValue::new(value).into(),
+                    None,
+                )))
+            }
+            NewEntry::Manual => {
+                let ident_input = inquire::Text::new("Name of field:")
+                    .with_help_message("The type of the field (username, password, etc)")
                     .prompt()?;
-                Ok(Some((ident_input, StoredValue::new(value_input).into())))
+                let kind = Self::prompt_kind(FieldKind::infer(&ident_input))?;
+                let value_input = Self::prompt_value("Value for field:", kind, strength)?;
+                Ok(Some((
+                    ident_input,
+                    kind,
+                    StoredValue::new(value_input).into(),
+                    None,
+                )))
             }
         }
     }
 
-    fn prompt_update(orig: &[String], spec: &PasswordSpec) -> anyhow::Result<Changes> {
-        let mut changes = Changes::new(orig);
+    /// the spec given, otherwise the one stored with the field or the default
+    fn field_spec(
+        spec: Option<&PasswordSpec>,
+        stored: Option<&String>,
+        default: &PasswordSpec,
+    ) -> anyhow::Result<PasswordSpec> {
+        match (spec, stored) {
+            (Some(spec), _) => Ok(spec.clone()),
+            (None, Some(stored)) => Ok(PasswordSpec::from_str(stored)?),
+            (None, None) => Ok(default.clone()),
+        }
+    }
+
+    fn prompt_kind(default: FieldKind) -> anyhow::Result<FieldKind> {
+        let kinds: Vec<FieldKind> = all::<FieldKind>().collect();
+        let cursor = kinds.iter().position(|k| *k == default).unwrap_or_default();
+        let kind = inquire::Select::new("Kind of field:", kinds)
+            .with_starting_cursor(cursor)
+            .prompt()?;
+        Ok(kind)
+    }
+
+    /// prompt for a value of the given kind, hiding the input for secret kinds
+    ///
+    /// Passwords weaker than the vault's strength policy are refused when it's enforced, otherwise
+    /// they're only kept after confirming.
+    fn prompt_value(
+        prompt: &str,
+        kind: FieldKind,
+        strength: StrengthPolicy,
+    ) -> anyhow::Result<String> {
+        let validator = move |value: &str| {
+            if !kind.is_valid(value) {
+                return Ok(Validation::Invalid(format!("Not a valid {kind}").into()));
+            }
+            let estimate = Strength::estimate(value);
+            if kind == FieldKind::Password && strength.enforce && !strength.accepts(&estimate) {
+                return Ok(Validation::Invalid(
+                    format!(
+                        "Too weak, {} where the vault requires {:.0} bits",
+                        Self::describe_strength(&estimate),
+                        strength.min_bits
+                    )
+                    .into(),
+                ));
+            }
+            Ok(Validation::Valid)
+        };
+        loop {
+            let value = if kind.is_secret() {
+                inquire::Password::new(prompt)
+                    .without_confirmation()
+                    .with_display_toggle_enabled()
+                    .with_display_mode(inquire::PasswordDisplayMode::Masked)
+                    .with_validator(validator)
+                    .prompt()?
+            } else {
+                inquire::Text::new(prompt)
+                    .with_help_message(&format!("The {kind} to be stored."))
+                    .with_validator(validator)
+                    .prompt()?
+            };
+            let estimate = Strength::estimate(&value);
+            if kind != FieldKind::Password || strength.accepts(&estimate) {
+                return Ok(value);
+            }
+            println!("Weak password: {}", Self::describe_strength(&estimate));
+            if inquire::Confirm::new("Keep it anyway?")
+                .with_default(false)
+                .prompt()?
+            {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// the estimate, its rating and what made it weaker if anything did
+    fn describe_strength(strength: &Strength) -> String {
+        match strength.pattern {
+            Some(pattern) => format!("{strength}, {} ({pattern})", strength.rating()),
+            None => format!("{strength}, {}", strength.rating()),
+        }
+    }
+
+    /// Prompt for changes to an entry, generated values use `spec` when given, otherwise the spec
+    /// the field was generated with or `default`
+    fn prompt_update(
+        schema: &Schema,
+        key: &str,
+        spec: Option<&PasswordSpec>,
+        default: &PasswordSpec,
+        passphrase: &PassphraseSpec,
+    ) -> anyhow::Result<Changes> {
+        let orig = schema.get(key).cloned().unwrap_or_default();
+        let mut changes = Changes::new(&orig);
+        let mut kinds: BTreeMap<String, FieldKind> = orig
+            .iter()
+            .map(|field| (field.to_string(), schema.kind(key, field)))
+            .collect();
+        let mut specs: BTreeMap<String, String> =
+            schema.specs.get(key).cloned().unwrap_or_default();
         loop {
             let mut fields: Vec<FieldChoice> = changes
                 .fields()
@@ -500,11 +1366,24 @@ impl CliApp {
             match field_choice {
                 FieldChoice::Done => break,
                 FieldChoice::New => {
-                    if let Some((k, v)) = Self::prompt_new_entry(spec)? {
+                    let spec = spec.unwrap_or(default);
+                    if let Some((k, kind, v, spec)) =
+                        Self::prompt_new_entry(spec, passphrase, schema.strength)?
+                    {
+                        changes.set_kind(&k, kind);
+                        kinds.insert(k.clone(), kind);
                         changes.insert(&k, v);
+                        if let Some(spec) = &spec {
+                            specs.insert(k.clone(), spec.clone());
+                        }
+                        changes.set_spec(&k, spec);
                     }
                 }
                 FieldChoice::Existing(s) => {
+                    let kind = kinds
+                        .get(&s)
+                        .copied()
+                        .unwrap_or_else(|| FieldKind::infer(&s));
                     let choice = inquire::Select::new(
                         &format!("How to update {s}:"),
                         all::<UpdateEntry>().collect(),
@@ -516,11 +1395,51 @@ impl CliApp {
                             changes.remove(&s);
                         }
                         UpdateEntry::Manual => {
-                            let value_input = inquire::Text::new("New value:").prompt()?;
+                            let value_input =
+                                Self::prompt_value("New value:", kind, schema.strength)?;
                             changes.insert(&s, StoredValue::new(value_input).into());
                         }
                         UpdateEntry::Generate => {
+                            let spec = Self::field_spec(spec, specs.get(&s), default)?;
                             let value = spec.generate().ok_or(ClientError::BadPasswordSpec)?;
+                            changes.insert(&s, StoredValue::new(value).into());
+                            specs.insert(s.clone(), spec.to_string());
+                            changes.set_spec(&s, Some(spec.to_string()));
+                        }
+                        UpdateEntry::Spec => {
+                            let current = specs
+                                .get(&s)
+                                .cloned()
+                                .unwrap_or_else(|| default.to_string());
+                            let input = inquire::Text::new(&format!("Spec for {s}:"))
+                                .with_initial_value(&current)
+                                .with_help_message(
+                                    "Used when regenerating, leave empty to forget it",
+                                )
+                                .with_validator(|value: &str| {
+                                    if value.trim().is_empty()
+                                        || PasswordSpec::from_str(value).is_ok()
+                                    {
+                                        Ok(Validation::Valid)
+                                    } else {
+                                        Ok(Validation::Invalid("Not a valid spec".into()))
+                                    }
+                                })
+                                .prompt()?;
+                            let new = match input.trim() {
+                                "" => None,
+                                input => Some(PasswordSpec::from_str(input)?.to_string()),
+                            };
+                            if new.as_ref() != specs.get(&s) {
+                                match &new {
+                                    Some(new) => specs.insert(s.clone(), new.clone()),
+                                    None => specs.remove(&s),
+                                };
+                                changes.set_spec(&s, new);
+                            }
+                        }
+                        UpdateEntry::Passphrase => {
+                            let value = passphrase.generate()?;
                             changes.insert(&s, StoredValue::new(value).into())
                         }
                         UpdateEntry::Move => {
@@ -533,6 +1452,13 @@ impl CliApp {
                             let value = inquire::Select::new("Swap with:", choices).prompt()?;
                             changes.swap(&s, &value);
                         }
+                        UpdateEntry::Kind => {
+                            let new_kind = Self::prompt_kind(kind)?;
+                            if new_kind != kind {
+                                changes.set_kind(&s, new_kind);
+                                kinds.insert(s.clone(), new_kind);
+                            }
+                        }
                     }
                 }
             }
@@ -541,17 +1467,12 @@ impl CliApp {
         Ok(changes)
     }
 
-    fn get_schema(manager: &mut VaultManager, vault: String) -> anyhow::Result<Schema> {
-        match manager.receive(ManagerMessage::VaultMessage(vault, Message::Schema))? {
-            Output::Schema(schema) => Ok(schema),
-            _ => Err(Box::new(CommunicationError::UnexpectedOutput).into()),
+    fn print_url_matches(matches: Vec<UrlMatch>, url: &Site) {
+        if matches.is_empty() {
+            println!("No entries for {url}");
         }
-    }
-
-    fn get_info(manager: &mut VaultManager) -> anyhow::Result<Info> {
-        match manager.receive(ManagerMessage::Info)? {
-            Output::Info(info) => Ok(info),
-            _ => Err(Box::new(CommunicationError::UnexpectedOutput).into()),
+        for found in matches {
+            println!("{found}");
         }
     }
 
@@ -7,6 +7,8 @@ use enum_iterator::Sequence;
 pub enum NewEntry {
     /// generate an entry based off password spec
     Generated,
+    /// generate a passphrase of random words
+    Passphrase,
     /// enter a value manually
     Manual,
     /// stop processing
@@ -17,6 +19,7 @@ impl Display for NewEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Generated => write!(f, "Randomly generated"),
+            Self::Passphrase => write!(f, "Random passphrase"),
             Self::Manual => write!(f, "Manually entered"),
             Self::Done => write!(f, "Done"),
         }
@@ -28,6 +31,8 @@ impl Display for NewEntry {
 pub enum UpdateEntry {
     /// randomly generate the value
     Generate,
+    /// generate a passphrase of random words
+    Passphrase,
     /// manually enter the value
     Manual,
     /// delete the entry
@@ -36,6 +41,10 @@ pub enum UpdateEntry {
     Swap,
     /// move the entry to be at the position of another
     Move,
+    /// change what kind of value the entry holds
+    Kind,
+    /// change the spec the value is regenerated with
+    Spec,
     /// do nothing
     Cancel,
 }
@@ -45,9 +54,12 @@ impl Display for UpdateEntry {
         match self {
             Self::Delete => write!(f, "Remove entry"),
             Self::Generate => write!(f, "Randomize value"),
+            Self::Passphrase => write!(f, "Random passphrase"),
             Self::Manual => write!(f, "Manually enter value"),
             Self::Swap => write!(f, "Swap position with another entry"),
             Self::Move => write!(f, "Move position to another entry"),
+            Self::Kind => write!(f, "Change kind of entry"),
+            Self::Spec => write!(f, "Change password spec"),
             Self::Cancel => write!(f, "Cancel"),
         }
     }
@@ -3,7 +3,10 @@ use figment::{
     Error, Metadata, Profile, Provider,
 };
 use pants_gen::password::PasswordSpec;
-use pants_store::config::internal_config::{BaseConfig, InternalConfig};
+use pants_store::{
+    config::internal_config::{BaseConfig, InternalConfig},
+    passphrase::PassphraseSpec,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -11,6 +14,7 @@ pub struct ClientConfig {
     pub password_spec: String,
     // seconds
     pub clipboard_time: u64,
+    pub passphrase: PassphraseSpec,
 }
 
 impl Default for ClientConfig {
@@ -18,6 +22,7 @@ impl Default for ClientConfig {
         Self {
             password_spec: PasswordSpec::default().to_string(),
             clipboard_time: 10,
+            passphrase: PassphraseSpec::default(),
         }
     }
 }
@@ -1,6 +1,9 @@
 use clap::Parser;
 use once_cell::sync::Lazy;
+use std::path::PathBuf;
+
 use pants_gen::{choice::Choice, interval::Interval, password::PasswordSpec};
+use pants_store::passphrase::PassphraseSpec;
 
 static DEFAULT_SPEC: Lazy<String> = Lazy::new(|| PasswordSpec::default().to_string());
 
@@ -45,6 +48,25 @@ pub struct CliArgs {
     /// constraints on custom characters, INTERVAL|CHARSET
     #[arg(short, long)]
     custom: Vec<Choice>,
+    /// generate a passphrase of random words instead, the defaults come from the `passphrase`
+    /// section of the client config
+    #[arg(long)]
+    passphrase: bool,
+    /// number of words in the passphrase
+    #[arg(long, requires = "passphrase")]
+    words: Option<usize>,
+    /// put between the words of the passphrase
+    #[arg(long, requires = "passphrase")]
+    separator: Option<String>,
+    /// start every word of the passphrase with a capital letter
+    #[arg(long, requires = "passphrase")]
+    capitalize: bool,
+    /// random digits added to the words of the passphrase
+    #[arg(long, requires = "passphrase")]
+    digits: Option<usize>,
+    /// file with the words to pick from, one per line
+    #[arg(long, requires = "passphrase")]
+    wordlist: Option<PathBuf>,
 }
 
 impl CliArgs {
@@ -53,6 +75,30 @@ impl CliArgs {
         args.execute()
     }
 
+    /// the passphrase spec asked for, starting from `base`, [None] when generating a password
+    pub fn passphrase(&self, base: PassphraseSpec) -> Option<PassphraseSpec> {
+        if !self.passphrase {
+            return None;
+        }
+        let mut spec = base;
+        if let Some(words) = self.words {
+            spec = spec.words(words);
+        }
+        if let Some(separator) = &self.separator {
+            spec = spec.separator(separator.clone());
+        }
+        if self.capitalize {
+            spec = spec.capitalize(true);
+        }
+        if let Some(digits) = self.digits {
+            spec = spec.digits(digits);
+        }
+        if let Some(wordlist) = &self.wordlist {
+            spec = spec.wordlist(wordlist.clone());
+        }
+        Some(spec)
+    }
+
     pub fn execute(self) -> Option<String> {
         let mut password_spec = self.spec;
         if let Some(length) = &self.length {
@@ -2,7 +2,7 @@ use std::{thread, time::Duration};
 
 use arboard::Clipboard;
 use clap::ValueEnum;
-use pants_store::{reads::Reads, store::Store};
+use pants_store::{reads::Reads, store::Store, totp::Code};
 use secrecy::ExposeSecret;
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -23,7 +23,11 @@ impl OutputStyle {
                 let mut clipboard = Clipboard::new()?;
                 let orig = clipboard.get_text().unwrap_or("".to_string());
                 for (key, value) in reads.data.clone().into_iter() {
-                    for (ident, item) in value.data {
+                    for (ident, item) in &value.data {
+                        if !value.kind(ident).is_secret() {
+                            println!("{key}-{ident}: {}", item.expose_secret());
+                            continue;
+                        }
                         clipboard.set_text(item.expose_secret().to_string())?;
 
                         if inquire::Text::new(&format!(
@@ -41,7 +45,51 @@ impl OutputStyle {
                 thread::sleep(Duration::from_secs(1));
             }
             OutputStyle::Raw => {
-                println!("{:?}", reads);
+                for (key, value) in reads.data.iter() {
+                    println!("{key}:");
+                    for (ident, item) in &value.data {
+                        println!(
+                            " - {ident} ({}): {}",
+                            value.kind(ident),
+                            item.expose_secret()
+                        );
+                    }
+                }
+            }
+            OutputStyle::None => {}
+        }
+        Ok(())
+    }
+
+    pub fn handle_otp(&self, codes: Vec<(String, Code)>) -> anyhow::Result<()> {
+        if codes.is_empty() {
+            println!("No TOTP fields in the entry");
+            return Ok(());
+        }
+        match self {
+            OutputStyle::Clipboard => {
+                let mut clipboard = Clipboard::new()?;
+                let orig = clipboard.get_text().unwrap_or("".to_string());
+                for (name, code) in codes {
+                    clipboard.set_text(code.code.clone())?;
+                    if inquire::Text::new(&format!(
+                        "Copied `{name}` code to clipboard, valid for {}s, hit enter to continue",
+                        code.remaining
+                    ))
+                    .prompt()
+                    .is_err()
+                    {
+                        break;
+                    }
+                }
+                clipboard.set_text(orig)?;
+                println!("Resetting clipboard");
+                thread::sleep(Duration::from_secs(1));
+            }
+            OutputStyle::Raw => {
+                for (name, code) in codes {
+                    println!("{name}: {code}");
+                }
             }
             OutputStyle::None => {}
         }
@@ -4,7 +4,10 @@ use figment::{
 };
 use iced::Theme;
 use pants_gen::password::PasswordSpec;
-use pants_store::config::internal_config::{BaseConfig, InternalConfig};
+use pants_store::{
+    config::internal_config::{BaseConfig, InternalConfig},
+    passphrase::PassphraseSpec,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -12,6 +15,7 @@ pub struct ClientConfig {
     pub password_spec: String,
     // seconds
     pub clipboard_time: u64,
+    pub passphrase: PassphraseSpec,
     pub theme: String,
 }
 
@@ -20,6 +24,7 @@ impl Default for ClientConfig {
         Self {
             password_spec: PasswordSpec::default().to_string(),
             clipboard_time: 10,
+            passphrase: PassphraseSpec::default(),
             theme: Theme::default().to_string(),
         }
     }
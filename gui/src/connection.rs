@@ -3,46 +3,68 @@ use iced::{
     subscription::{self, Subscription},
 };
 
-use pants_store::{manager_message::ManagerMessage, output::Output, vault::manager::VaultManager};
+use pants_store::{client::PantsClient, info::Info, store::Store, Password};
+
+// events are handled one at a time, boxing the info would only add noise at every use
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum Event {
     Connected(Connection),
     Disconnected,
-    ReceiveOutput(Output),
+    ReceiveInfo(Info),
+    /// value of the entry that was asked for
+    ReceiveEntry(String, Store),
+    /// the request went through and had nothing to report
+    Done,
     ReceiveError(String),
-    // ReceiveSchema(Schema),
-    // ReceiveRead(Reads<Store>),
-    // ReceiveList(Vec<String>),
-    // ReceiveBackup(BackupFile),
-    // ReceiveBackupFiles(Vec<BackupFile>),
-    // ReceiveNothing,
 }
 
-impl From<Output> for Event {
-    fn from(value: Output) -> Self {
-        Self::ReceiveOutput(value)
-        // match value {
-        //     Output::Schema(s) => Self::ReceiveSchema(s),
-        //     Output::Read(r) => Self::ReceiveRead(r),
-        //     Output::List(l) => Self::ReceiveList(l),
-        //     Output::Backup(b) => Self::ReceiveBackup(b),
-        //     Output::BackupFiles(f) => Self::ReceiveBackupFiles(f),
-        //     Output::Nothing => Self::ReceiveNothing,
-        // }
+/// what the GUI asks of the vaults, the first field is the vault name
+#[derive(Debug)]
+pub enum Request {
+    Info,
+    NewVault(String),
+    Get(String, String, Password),
+    Update(String, String, Password, Store),
+    Delete(String, String, Password),
+    DeleteVault(String, Password),
+    DeleteEmptyVault(String),
+}
+
+impl Request {
+    fn handle(self, client: &mut PantsClient) -> anyhow::Result<Event> {
+        match self {
+            Self::Info => Ok(Event::ReceiveInfo(client.info()?)),
+            Self::NewVault(vault) => client.new_vault(&vault).map(|_| Event::Done),
+            Self::Get(vault, key, password) => {
+                let value = client.get(&vault, &key, password)?;
+                Ok(Event::ReceiveEntry(key, value))
+            }
+            Self::Update(vault, key, password, value) => client
+                .update(&vault, &key, password, value)
+                .map(|_| Event::Done),
+            Self::Delete(vault, key, password) => {
+                client.delete(&vault, &key, password).map(|_| Event::Done)
+            }
+            Self::DeleteVault(vault, password) => {
+                client.delete_vault(&vault, password).map(|_| Event::Done)
+            }
+            Self::DeleteEmptyVault(vault) => client.delete_empty_vault(&vault).map(|_| Event::Done),
+        }
     }
 }
 
 #[derive(Debug)]
 enum State {
     Starting,
-    Connected(mpsc::Receiver<ManagerMessage>),
+    Connected(mpsc::Receiver<Request>),
 }
 
 #[derive(Debug, Clone)]
-pub struct Connection(mpsc::Sender<ManagerMessage>);
+pub struct Connection(mpsc::Sender<Request>);
 
 impl Connection {
-    pub fn send(&mut self, message: ManagerMessage) {
+    pub fn send(&mut self, message: Request) {
         self.0
             .try_send(message)
             .expect("Send message to echo server");
@@ -50,7 +72,7 @@ impl Connection {
 }
 pub fn connect() -> Subscription<Event> {
     struct Connect;
-    let mut interface = VaultManager::default();
+    let mut client = PantsClient::default();
     subscription::channel(
         std::any::TypeId::of::<Connect>(),
         100,
@@ -70,11 +92,10 @@ pub fn connect() -> Subscription<Event> {
 
                         let input = receiver.select_next_some().await;
 
-                        let response = interface.receive(input);
+                        let response = input.handle(&mut client);
 
                         match response {
-                            Ok(vault_output) => {
-                                let event = vault_output.into();
+                            Ok(event) => {
                                 let _ = output.send(event).await;
                             }
                             // TODO: actually pass along errors so they can be reacted to and
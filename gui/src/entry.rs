@@ -1,12 +1,21 @@
+use std::collections::BTreeSet;
+
 use iced::{
     theme,
     widget::{button, container, row, text},
     Element, Length,
 };
+use pants_store::{policy::AgeStatus, store::in_folder};
 
 #[derive(Debug, Clone)]
 pub struct Entry {
     pub key: String,
+    pub tags: BTreeSet<String>,
+    pub folder: Option<String>,
+    /// values of the public fields, shown next to the name
+    pub public: Vec<String>,
+    /// how close the entry is to its maximum age, when it's near it
+    pub due: Option<AgeStatus>,
 }
 
 #[derive(Debug, Clone)]
@@ -16,17 +25,47 @@ pub enum EntryMessage {
 }
 
 impl Entry {
-    pub fn new(key: String, _style: String) -> Self {
-        Entry { key }
+    pub fn new(
+        key: String,
+        tags: BTreeSet<String>,
+        folder: Option<String>,
+        public: Vec<String>,
+        due: Option<AgeStatus>,
+    ) -> Self {
+        Entry {
+            key,
+            tags,
+            folder,
+            public,
+            due,
+        }
+    }
+
+    /// whether the entry's name or a public value contains the term, it has the term as a tag, or
+    /// it's in the folder named by the term
+    pub fn matches(&self, term: &str) -> bool {
+        self.key.contains(term)
+            || self.public.iter().any(|value| value.contains(term))
+            || self.tags.contains(term)
+            || in_folder(self.folder.as_deref(), term)
     }
 
-    pub fn view(&self) -> Element<EntryMessage> {
-        let value = text(self.key.clone()).width(Length::Fill);
+    pub fn view(&self) -> Element<'_, EntryMessage> {
+        let value = text(self.key.clone());
+        let public = text(self.public.join(", ")).width(Length::Fill);
+        let tags = text(
+            self.tags
+                .iter()
+                .map(|tag| format!("#{tag}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+        let due = text(self.due.map(|due| format!("({due})")).unwrap_or_default());
         let view_button = button("View").on_press(EntryMessage::View);
         let delete_button = button("Delete")
             .on_press(EntryMessage::Delete)
             .style(theme::Button::Destructive);
-        let content = row![view_button, value, delete_button];
+        let content = row![view_button, value, public, due, tags, delete_button].spacing(5);
         container(content)
             .width(Length::Fill)
             .height(Length::Shrink)
@@ -1,7 +1,7 @@
 use iced::Theme;
 use secrecy::Secret;
 
-use pants_store::Password;
+use pants_store::{store::StoreChoice, Password};
 
 use super::{connection, vault::VaultMessage};
 
@@ -19,8 +19,15 @@ pub enum GUIMessage {
     PasswordConfirmChanged(Password),
     ChangeName(String),
     SelectStyle(StoreChoice),
+    SelectTemplate(String),
+    /// show only entries matching the terms, by name, tag or folder
+    Filter(String),
     UpdateField(String, Secret<String>),
     GeneratePassword,
+    /// change the spec the password is generated with
+    UpdateSpec(String),
+    /// fill the password with a passphrase of random words
+    GeneratePassphrase,
     CopyClipboard(Option<Password>),
     ClearClipboard,
     ChangeTheme(Theme),
@@ -28,13 +35,8 @@ pub enum GUIMessage {
     ClosePopup,
     TabPressed(bool),
     Close,
+    /// redraw time dependent views like one-time codes
+    Tick,
     Nothing,
     // Send(Message),
 }
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum StoreChoice {
-    Password,
-    UsernamePassword,
-    Generic,
-}
@@ -1,11 +1,13 @@
 use iced::{
-    widget::{button, column, container, row, text, text_input},
+    widget::{button, column, container, progress_bar, row, text, text_input, Column, Row},
     Element, Length,
 };
 use secrecy::ExposeSecret;
 
 use pants_store::{
-    store::{Store, StoreChoice, StoreHash},
+    store::{FieldKind, Store, StoreChoice, StoreHash},
+    strength::Strength,
+    totp::Totp,
     Password,
 };
 
@@ -21,7 +23,7 @@ pub struct EntryState {
 }
 
 impl EntryState {
-    pub fn view(&self) -> Element<GUIMessage> {
+    pub fn view(&self) -> Element<'_, GUIMessage> {
         let header = text(format!("{} in {}", self.key.clone(), self.vault));
         let data_input = match &self.choice {
             StoreChoice::Password => {
@@ -41,12 +43,19 @@ impl EntryState {
                 };
                 let copy_button = button("Copy").on_press(GUIMessage::CopyPassword);
                 let password_generate = button("Generate").on_press(GUIMessage::GeneratePassword);
-                container(row![
-                    prefix,
-                    password_input,
-                    password_generate,
-                    copy_button,
-                    show_button
+                let passphrase_generate =
+                    button("Passphrase").on_press(GUIMessage::GeneratePassphrase);
+                container(column![
+                    row![
+                        prefix,
+                        password_input,
+                        password_generate,
+                        passphrase_generate,
+                        copy_button,
+                        show_button
+                    ],
+                    strength_meter(self.value.get("password").unwrap().expose_secret()),
+                    spec_input(&self.value)
                 ])
             }
             StoreChoice::UsernamePassword => {
@@ -72,17 +81,44 @@ impl EntryState {
                 };
                 let copy_button = button("Copy").on_press(GUIMessage::CopyPassword);
                 let password_generate = button("Generate").on_press(GUIMessage::GeneratePassword);
+                let passphrase_generate =
+                    button("Passphrase").on_press(GUIMessage::GeneratePassphrase);
                 container(column![
                     row![username_prefix, username_input],
                     row![
                         password_prefix,
                         password_input,
                         password_generate,
+                        passphrase_generate,
                         copy_button,
                         show_button
-                    ]
+                    ],
+                    strength_meter(self.value.get("password").unwrap().expose_secret()),
+                    spec_input(&self.value)
                 ])
             }
+            StoreChoice::Generic => {
+                let mut content = field_inputs(&self.value, self.hidden);
+                if self.value.password().is_some() {
+                    let show_button = if self.hidden {
+                        button("Show").on_press(GUIMessage::ShowPassword)
+                    } else {
+                        button("Hide").on_press(GUIMessage::HidePassword)
+                    };
+                    let copy_button = button("Copy").on_press(GUIMessage::CopyPassword);
+                    let password_generate =
+                        button("Generate").on_press(GUIMessage::GeneratePassword);
+                    let passphrase_generate =
+                        button("Passphrase").on_press(GUIMessage::GeneratePassphrase);
+                    content = content.push(spec_input(&self.value)).push(row![
+                        password_generate,
+                        passphrase_generate,
+                        copy_button,
+                        show_button
+                    ]);
+                }
+                container(content)
+            }
         };
 
         let save_button = button("Save").on_press(GUIMessage::Submit);
@@ -101,23 +137,17 @@ impl EntryState {
         self.value = value;
     }
 
+    pub fn has_totp(&self) -> bool {
+        self.value
+            .fields()
+            .any(|(_, kind, _)| kind == FieldKind::Totp)
+    }
+
     pub fn get_password(&self) -> Option<Password> {
-        for (key, value) in self.value.iter() {
-            if key == "password" {
-                return Some(value.clone());
-            }
-        }
-        None
+        self.value.password().cloned()
     }
 
-    pub fn from_entry(vault: String, key: String, style: String) -> Self {
-        let value = match style.as_str() {
-            "password" => Store::Password(String::new().into()),
-            "username-password" => {
-                Store::UsernamePassword(String::new().into(), String::new().into())
-            }
-            _ => panic!("unrecognized entry value {}", style),
-        };
+    pub fn from_entry(vault: String, key: String, value: Store) -> Self {
         let (choice, value) = value.split();
         EntryState {
             vault,
@@ -128,3 +158,63 @@ impl EntryState {
         }
     }
 }
+
+/// an input for each field, hiding the values of secret kinds
+pub fn field_inputs(value: &StoreHash, hidden: bool) -> Column<'_, GUIMessage> {
+    let mut content = column![];
+    for (key, kind, field) in value.fields() {
+        let prefix = text(format!("{key}:"));
+        let name = key.to_string();
+        let input = text_input(&kind.to_string(), field.expose_secret())
+            .width(Length::Fill)
+            .on_input(move |v| GUIMessage::UpdateField(name.clone(), v.into()))
+            .secure(kind.is_secret() && hidden);
+        content = content.push(row![prefix, input]);
+        if kind == FieldKind::Password {
+            content = content.push(strength_meter(field.expose_secret()));
+        }
+        if kind == FieldKind::Totp {
+            if let Ok(totp) = field.expose_secret().parse::<Totp>() {
+                let code = totp.code();
+                let copy_button = button("Copy")
+                    .on_press(GUIMessage::CopyClipboard(Some(code.code.clone().into())));
+                content = content.push(row![
+                    text(format!("Code: {}", code.code)).width(Length::Fill),
+                    text(format!("{}s", code.remaining)),
+                    copy_button
+                ]);
+            }
+        }
+    }
+    content
+}
+
+/// estimated strength of a password as a bar, with what weakens it
+pub fn strength_meter(password: &str) -> Row<'_, GUIMessage> {
+    let strength = Strength::estimate(password);
+    let label = match strength.pattern {
+        _ if password.is_empty() => String::new(),
+        Some(pattern) => format!("{}, {pattern}", strength.rating()),
+        None => strength.rating().to_string(),
+    };
+    row![
+        progress_bar(0.0..=100.0, strength.bits.min(100.0) as f32).height(Length::Fixed(8.0)),
+        text(label).size(12)
+    ]
+    .spacing(8)
+    .align_items(iced::Alignment::Center)
+}
+
+/// the spec the password is generated with, empty to use the configured one
+pub fn spec_input(value: &StoreHash) -> Row<'_, GUIMessage> {
+    let spec = value
+        .password_spec()
+        .map(|spec| spec.as_str())
+        .unwrap_or_default();
+    row![
+        text("Spec:"),
+        text_input("Configured spec", spec)
+            .width(Length::Fill)
+            .on_input(GUIMessage::UpdateSpec)
+    ]
+}
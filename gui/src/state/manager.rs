@@ -1,10 +1,10 @@
 use boring_derive::From;
-use std::{collections::BTreeMap, str::FromStr};
+use std::{collections::BTreeMap, str::FromStr, time::Duration};
 
 use super::prompt::PromptState;
 use crate::{
     client_config::ClientConfig,
-    connection,
+    connection::{self, Request},
     entry::EntryMessage,
     gui_message::GUIMessage,
     state::{entry::EntryState, new_entry::NewEntryState, password::PasswordState},
@@ -26,20 +26,23 @@ use iced_aw::{
 use iced_futures::MaybeSend;
 use pants_gen::password::PasswordSpec;
 use pants_store::{
-    config::internal_config::{BaseConfig, InternalConfig},
+    config::{
+        internal_config::{BaseConfig, InternalConfig},
+        template_config::TemplateConfig,
+    },
     info::Info,
-    manager_message::ManagerMessage,
-    output::Output,
-    reads::Reads,
-    store::{Store, StoreChoice},
+    store::{Store, StoreChoice, StoreHash},
     Password,
 };
 use secrecy::{ExposeSecret, Secret};
 
 pub struct ManagerState {
     config: ClientConfig,
+    templates: TemplateConfig,
     info: Info,
     vaults: BTreeMap<String, Vault>,
+    /// terms the listed entries are filtered by
+    filter: String,
     internal_state: Vec<InternalState>,
     temp_message: TempMessage,
     stored_clipboard: Option<Password>,
@@ -50,10 +53,13 @@ pub struct ManagerState {
 impl Default for ManagerState {
     fn default() -> Self {
         let config: ClientConfig = <ClientConfig as BaseConfig>::load_err();
+        let templates: TemplateConfig = <TemplateConfig as BaseConfig>::load_err();
         Self {
             config,
+            templates,
             info: Info::default(),
             vaults: BTreeMap::new(),
+            filter: String::new(),
             internal_state: Vec::new(),
             temp_message: TempMessage::default(),
             stored_clipboard: None,
@@ -85,7 +91,7 @@ impl ManagerState {
                     EntryState::from_entry(
                         vault.to_string(),
                         key.to_string(),
-                        self.info.get(vault).unwrap().get(key).unwrap().to_string(),
+                        self.info.get(vault).unwrap().blank(key).unwrap(),
                     )
                     .into(),
                 );
@@ -101,19 +107,19 @@ impl ManagerState {
                 let message = self.temp_message.with_password(password);
                 self.internal_state = vec![];
                 self.temp_message = TempMessage::default();
-                (Command::none(), vec![message, ManagerMessage::Info])
+                (Command::none(), vec![message, Request::Info])
             }
             TempMessage::New(..) => {
                 let message = self.temp_message.with_password(password);
                 self.internal_state = vec![];
                 self.temp_message = TempMessage::default();
-                (Command::none(), vec![message, ManagerMessage::Info])
+                (Command::none(), vec![message, Request::Info])
             }
             TempMessage::Update(..) => {
                 let message = self.temp_message.with_password(password);
                 self.internal_state = vec![];
                 self.temp_message = TempMessage::default();
-                (Command::none(), vec![message, ManagerMessage::Info])
+                (Command::none(), vec![message, Request::Info])
             }
             TempMessage::Empty => {
                 self.internal_state = vec![];
@@ -124,14 +130,14 @@ impl ManagerState {
                 let message = self.temp_message.with_password(password);
                 self.internal_state = vec![];
                 self.temp_message = TempMessage::default();
-                (Command::none(), vec![message, ManagerMessage::Info])
+                (Command::none(), vec![message, Request::Info])
             }
             // non-sense
             TempMessage::DeleteEmptyVault(..) => {
                 let message = self.temp_message.with_password(password);
                 self.internal_state = vec![];
                 self.temp_message = TempMessage::default();
-                (Command::none(), vec![message, ManagerMessage::Info])
+                (Command::none(), vec![message, Request::Info])
             }
         };
         self.send_message(messages);
@@ -161,31 +167,49 @@ impl ManagerState {
         self.vaults = vaults;
         self.info = info;
     }
-    fn update_entry(&mut self, data: Reads<Store>) {
+    fn update_entry(&mut self, key: String, value: Store) {
         // TODO: check if robust, could be that a response was given to a lower down state, but I
         // find it unlikely it will get to be that way
         if let Some(InternalState::Entry(entry)) = self.active_state_mut() {
-            for (key, value) in &data.data {
-                if *key == entry.key {
-                    entry.update(value.clone());
-                }
+            if key == entry.key {
+                entry.update(value.clone());
             }
         }
-        // seems dumb to loop twice
         if let TempMessage::Update(_, update_key, ref mut choice, ref mut update_value) =
             &mut self.temp_message
         {
-            for (key, value) in data.data {
-                if *update_key == key {
-                    let (new_choice, new_values) = value.split();
-                    *choice = new_choice;
-                    *update_value = new_values;
-                }
+            if *update_key == key {
+                let (new_choice, new_values) = value.split();
+                *choice = new_choice;
+                *update_value = new_values;
             }
         }
     }
 
-    fn send_message(&mut self, messages: Vec<ManagerMessage>) {
+    /// values of the entry being edited
+    fn editing(&self) -> Option<&StoreHash> {
+        match self.active_state() {
+            Some(InternalState::New(new_state)) => Some(&new_state.value),
+            Some(InternalState::Entry(entry_state)) => Some(&entry_state.value),
+            _ => None,
+        }
+    }
+
+    /// change the entry being edited, both in its view and in the message that saves it
+    fn edit_value(&mut self, edit: impl Fn(&mut StoreHash)) {
+        match self.active_state_mut() {
+            Some(InternalState::New(new_state)) => edit(&mut new_state.value),
+            Some(InternalState::Entry(entry_state)) => edit(&mut entry_state.value),
+            _ => {}
+        };
+        match &mut self.temp_message {
+            TempMessage::New(_, _, _, ref mut value) => edit(value),
+            TempMessage::Update(_, _, _, ref mut value) => edit(value),
+            _ => {}
+        };
+    }
+
+    fn send_message(&mut self, messages: Vec<Request>) {
         for message in messages {
             match self.state {
                 ConnectionState::Disconnected => {}
@@ -205,7 +229,7 @@ impl ManagerState {
         text_input::focus(INPUT_ID.clone())
     }
 
-    fn view(&self) -> Element<GUIMessage> {
+    fn view(&self) -> Element<'_, GUIMessage> {
         let top_layer = self.internal_state.last().map(|state| state.view());
 
         let menu = |items| Menu::new(items).max_width(180.0).offset(0.0).spacing(0.0);
@@ -243,10 +267,18 @@ impl ManagerState {
         });
 
         // let new_vault = button("New Vault").on_press(GUIMessage::NewVault);
+        let filter = text_input("Filter by name, tag or folder", &self.filter)
+            .on_input(GUIMessage::Filter)
+            .padding(5);
+        let filter = container(filter).padding([5, 10]);
+        let vaults = self
+            .vaults
+            .values()
+            .filter(|v| self.filter.trim().is_empty() || v.matches(&self.filter));
         let content = scrollable(
-            column(self.vaults.values().map(|v| {
+            column(vaults.map(|v| {
                 container(
-                    v.view()
+                    v.view(self.filter.trim())
                         .map(move |message| GUIMessage::VaultMessage(message, v.name.clone())),
                 )
                 .padding(3)
@@ -256,7 +288,7 @@ impl ManagerState {
         );
 
         // let info = self.temp_message.view();
-        let primary = container(column![menu, content]);
+        let primary = container(column![menu, filter, content]);
         let main = modal(primary, top_layer)
             .backdrop(GUIMessage::Exit)
             .on_esc(GUIMessage::Exit)
@@ -317,8 +349,22 @@ fn close_popup() -> Command<GUIMessage> {
     delayed_command(5, |_| GUIMessage::ClosePopup)
 }
 
+/// fields for a newly selected entry type, keeping the values that carry over
+fn reshape(choice: StoreChoice, previous: &StoreHash) -> StoreHash {
+    let mut value = choice.convert_default().as_hash();
+    for (key, _, _) in choice.fields() {
+        if let Some(previous) = previous.get(key) {
+            value.insert(key.to_string(), previous.clone());
+        }
+        if let Some(spec) = previous.specs.get(*key) {
+            value.specs.insert(key.to_string(), spec.clone());
+        }
+    }
+    value
+}
+
 impl InternalState {
-    fn view(&self) -> Element<GUIMessage> {
+    fn view(&self) -> Element<'_, GUIMessage> {
         match self {
             Self::Password(password_state) => password_state.view(),
             Self::New(new_state) => new_state.view(),
@@ -348,23 +394,14 @@ impl Application for ManagerState {
             GUIMessage::Event(event) => match event {
                 connection::Event::Connected(connection) => {
                     self.state = ConnectionState::Connected(connection);
-                    self.send_message(vec![ManagerMessage::Info]);
+                    self.send_message(vec![Request::Info]);
                 }
                 connection::Event::Disconnected => {
                     self.state = ConnectionState::Disconnected;
                 }
-                connection::Event::ReceiveOutput(output) => match output {
-                    Output::Info(info) => {
-                        // println!("Received info: {:?}", info);
-                        self.update(info);
-                    }
-                    Output::Read(value) => {
-                        // println!("Received read: {:?}", value);
-                        self.update_entry(value);
-                    }
-                    Output::Nothing => {}
-                    _ => todo!(),
-                },
+                connection::Event::ReceiveInfo(info) => self.update(info),
+                connection::Event::ReceiveEntry(key, value) => self.update_entry(key, value),
+                connection::Event::Done => {}
 
                 connection::Event::ReceiveError(e) => {
                     self.internal_state = vec![];
@@ -400,16 +437,16 @@ impl Application for ManagerState {
                         StoreChoice::default(),
                         StoreChoice::default().convert_default().as_hash(),
                     );
-                    let command = self.push_internal_state(NewEntryState::for_vault(vault));
+                    let command = self.push_internal_state(NewEntryState::for_vault(
+                        vault,
+                        self.templates.names(),
+                    ));
                     let gen_password = delayed_command(0, |_| GUIMessage::GeneratePassword);
                     return Command::batch(vec![command, gen_password]);
                 }
                 VaultMessage::Delete => {
                     if self.info.get(&vault).unwrap().is_empty() {
-                        self.send_message(vec![
-                            ManagerMessage::DeleteEmptyVault(vault),
-                            ManagerMessage::Info,
-                        ]);
+                        self.send_message(vec![Request::DeleteEmptyVault(vault), Request::Info]);
                     } else {
                         self.temp_message = TempMessage::DeleteVault(vault);
                     }
@@ -451,11 +488,43 @@ impl Application for ManagerState {
             GUIMessage::SelectStyle(choice) => {
                 if let Some(InternalState::New(new_state)) = self.active_state_mut() {
                     new_state.choice = choice;
-                    // new_state.value = choice.convert_default().as_hash();
+                    new_state.template = None;
+                    new_state.value = reshape(choice, &new_state.value);
                 }
-                if let TempMessage::New(_, _, ref mut style, _) = &mut self.temp_message {
+                if let TempMessage::New(_, _, ref mut style, ref mut value) = &mut self.temp_message
+                {
                     *style = choice;
-                    // *value = choice.convert_default().as_hash();
+                    *value = reshape(choice, value);
+                }
+            }
+            GUIMessage::SelectTemplate(name) => {
+                if let Some(template) = self.templates.get(&name) {
+                    let spec = PasswordSpec::from_str(&self.config.password_spec).unwrap();
+                    let mut value = template.convert_default(|field| {
+                        if !field.generate {
+                            return None;
+                        }
+                        let spec = match &field.spec {
+                            Some(spec) => PasswordSpec::from_str(spec).ok()?,
+                            None => spec.clone(),
+                        };
+                        spec.generate().map(|p| p.into())
+                    });
+                    for field in template.fields.iter().filter(|field| field.generate) {
+                        let spec = field.spec.clone().unwrap_or_else(|| spec.to_string());
+                        value.specs.insert(field.name.clone(), spec);
+                    }
+                    if let Some(InternalState::New(new_state)) = self.active_state_mut() {
+                        new_state.choice = StoreChoice::Generic;
+                        new_state.template = Some(name);
+                        new_state.value = value.clone();
+                    }
+                    if let TempMessage::New(_, _, ref mut style, ref mut hash) =
+                        &mut self.temp_message
+                    {
+                        *style = StoreChoice::Generic;
+                        *hash = value;
+                    }
                 }
             }
             GUIMessage::UpdateField(k, v) => {
@@ -479,31 +548,40 @@ impl Application for ManagerState {
                 };
             }
             GUIMessage::GeneratePassword => {
-                let spec = PasswordSpec::from_str(&self.config.password_spec).unwrap();
-                let password: Secret<String> = spec.generate().unwrap().into();
-                match self.active_state_mut() {
-                    Some(InternalState::New(new_state)) => {
-                        new_state
-                            .value
-                            .insert("password".to_string(), password.clone());
-                    }
-                    Some(InternalState::Entry(entry_state)) => {
-                        entry_state
-                            .value
-                            .insert("password".to_string(), password.clone());
-                    }
-                    _ => {}
-                };
-                match &mut self.temp_message {
-                    TempMessage::New(_, _, _, ref mut value) => {
-                        value.insert("password".to_string(), password);
-                    }
-                    TempMessage::Update(_, _, _, ref mut value) => {
-                        value.insert("password".to_string(), password);
+                // entries remember the spec their password was generated with
+                let spec = self
+                    .editing()
+                    .and_then(|value| value.password_spec().cloned())
+                    .unwrap_or_else(|| self.config.password_spec.clone());
+                match PasswordSpec::from_str(&spec)
+                    .ok()
+                    .and_then(|s| s.generate())
+                {
+                    Some(password) => self.edit_value(|value| {
+                        value.set_password(password.clone().into());
+                        value.set_password_spec(Some(spec.clone()));
+                    }),
+                    None => {
+                        self.notice = Some(format!("Can't generate a password from `{spec}`"));
+                        return close_popup();
                     }
-                    _ => {}
-                };
+                }
+            }
+            GUIMessage::UpdateSpec(spec) => {
+                let spec = (!spec.trim().is_empty()).then_some(spec);
+                self.edit_value(|value| {
+                    value.set_password_spec(spec.clone());
+                });
             }
+            GUIMessage::GeneratePassphrase => match self.config.passphrase.generate() {
+                Ok(passphrase) => self.edit_value(|value| {
+                    value.set_password(passphrase.clone().into());
+                }),
+                Err(e) => {
+                    self.notice = Some(e.to_string());
+                    return close_popup();
+                }
+            },
 
             GUIMessage::Submit => {
                 if let Some(active_state) = self.active_state() {
@@ -565,8 +643,8 @@ impl Application for ManagerState {
                             if !self.info.data.contains_key(&prompt_state.vault)
                                 && !prompt_state.vault.is_empty()
                             {
-                                let message = ManagerMessage::NewVault(prompt_state.vault.clone());
-                                self.send_message(vec![message, ManagerMessage::Info]);
+                                let message = Request::NewVault(prompt_state.vault.clone());
+                                self.send_message(vec![message, Request::Info]);
                                 self.internal_state.pop();
                             } else {
                                 if self.info.data.contains_key(&prompt_state.vault) {
@@ -663,6 +741,7 @@ impl Application for ManagerState {
                 return iced::clipboard::write(contents.expose_secret().into());
             }
             GUIMessage::NewVault => return self.push_internal_state(PromptState::default()),
+            GUIMessage::Filter(filter) => self.filter = filter,
             GUIMessage::ChangeTheme(theme) => {
                 self.config.theme = theme.to_string();
                 if self.config.save().is_err() {
@@ -678,13 +757,13 @@ impl Application for ManagerState {
                 }
             }
             GUIMessage::Close => return window::close(window::Id::MAIN),
-            GUIMessage::Nothing => {}
+            GUIMessage::Tick | GUIMessage::Nothing => {}
         }
 
         Command::none()
     }
 
-    fn view(&self) -> Element<Self::Message> {
+    fn view(&self) -> Element<'_, Self::Message> {
         self.view()
     }
 
@@ -735,7 +814,14 @@ impl Application for ManagerState {
         //     // }
         // });
 
-        Subscription::batch(vec![connection_subscriber, keyboard_subscriber])
+        let mut subscriptions = vec![connection_subscriber, keyboard_subscriber];
+        if let Some(InternalState::Entry(entry_state)) = self.active_state() {
+            if entry_state.has_totp() {
+                subscriptions
+                    .push(iced::time::every(Duration::from_secs(1)).map(|_| GUIMessage::Tick));
+            }
+        }
+        Subscription::batch(subscriptions)
     }
 
     fn theme(&self) -> Theme {
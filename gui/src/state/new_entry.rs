@@ -6,13 +6,22 @@ use secrecy::ExposeSecret;
 
 use pants_store::store::{StoreChoice, StoreHash};
 
-use crate::{gui_message::GUIMessage, widget::card::Card, INPUT_ID};
+use crate::{
+    gui_message::GUIMessage,
+    state::entry::{field_inputs, spec_input, strength_meter},
+    widget::card::Card,
+    INPUT_ID,
+};
 
 #[derive(Debug, Clone)]
 pub struct NewEntryState {
     pub vault: String,
     pub name: String,
     pub choice: StoreChoice,
+    /// template the fields came from, if one was picked
+    pub template: Option<String>,
+    /// names of the configured templates
+    pub templates: Vec<String>,
     pub value: StoreHash,
     pub hidden: bool,
 }
@@ -23,6 +32,8 @@ impl Default for NewEntryState {
             vault: String::new(),
             name: String::new(),
             choice: StoreChoice::default(),
+            template: None,
+            templates: Vec::new(),
             value: StoreChoice::default().convert_default().as_hash(),
             hidden: true,
         }
@@ -30,27 +41,41 @@ impl Default for NewEntryState {
 }
 
 impl NewEntryState {
-    pub fn for_vault(vault: String) -> Self {
+    pub fn for_vault(vault: String, templates: Vec<String>) -> Self {
         NewEntryState {
             vault,
             name: String::new(),
             choice: StoreChoice::default(),
+            template: None,
+            templates,
             value: StoreChoice::default().convert_default().as_hash(),
             hidden: true,
         }
     }
-    pub fn view(&self) -> Element<GUIMessage> {
+    pub fn view(&self) -> Element<'_, GUIMessage> {
         let header = text(format!("New entry for {}", self.vault));
         let name_prefix = text("Name:");
         let name_input = text_input("Name", &self.name)
             .on_input(GUIMessage::ChangeName)
             .on_submit(GUIMessage::Submit)
             .id(INPUT_ID.clone());
+        // generic entries have no fixed fields to fill in
+        let choices: Vec<StoreChoice> = StoreChoice::all()
+            .into_iter()
+            .filter(|choice| *choice != StoreChoice::Generic)
+            .collect();
         let style_choice = pick_list(
-            StoreChoice::all(),
-            Some(self.choice),
+            choices,
+            self.template.is_none().then_some(self.choice),
             GUIMessage::SelectStyle,
-        );
+        )
+        .placeholder("Entry type");
+        let template_choice = pick_list(
+            self.templates.clone(),
+            self.template.clone(),
+            GUIMessage::SelectTemplate,
+        )
+        .placeholder("Template");
         let data_input = match &self.choice {
             StoreChoice::Password => {
                 let prefix = text("Password:");
@@ -63,13 +88,25 @@ impl NewEntryState {
                 .on_submit(GUIMessage::Submit)
                 .secure(self.hidden);
                 let password_generate = button("Generate").on_press(GUIMessage::GeneratePassword);
+                let passphrase_generate =
+                    button("Passphrase").on_press(GUIMessage::GeneratePassphrase);
                 let toggle_show = if self.hidden {
                     button("Show").on_press(GUIMessage::ShowPassword)
                 } else {
                     button("Hide").on_press(GUIMessage::HidePassword)
                 };
 
-                container(row![prefix, password_input, password_generate, toggle_show])
+                container(column![
+                    row![
+                        prefix,
+                        password_input,
+                        password_generate,
+                        passphrase_generate,
+                        toggle_show
+                    ],
+                    strength_meter(self.value.get("password").unwrap().expose_secret()),
+                    spec_input(&self.value)
+                ])
             }
             StoreChoice::UsernamePassword => {
                 let username_prefix = text("Username:");
@@ -91,6 +128,9 @@ impl NewEntryState {
                 .secure(self.hidden);
 
                 let password_generate = button("Generate").on_press(GUIMessage::GeneratePassword);
+
+                let passphrase_generate =
+                    button("Passphrase").on_press(GUIMessage::GeneratePassphrase);
                 let toggle_show = if self.hidden {
                     button("Show").on_press(GUIMessage::ShowPassword)
                 } else {
@@ -102,10 +142,33 @@ impl NewEntryState {
                         password_prefix,
                         password_input,
                         password_generate,
+                        passphrase_generate,
                         toggle_show
-                    ]
+                    ],
+                    strength_meter(self.value.get("password").unwrap().expose_secret()),
+                    spec_input(&self.value)
                 ])
             }
+            StoreChoice::Generic => {
+                let mut content = field_inputs(&self.value, self.hidden);
+                if self.value.password().is_some() {
+                    let password_generate =
+                        button("Generate").on_press(GUIMessage::GeneratePassword);
+                    let passphrase_generate =
+                        button("Passphrase").on_press(GUIMessage::GeneratePassphrase);
+                    let toggle_show = if self.hidden {
+                        button("Show").on_press(GUIMessage::ShowPassword)
+                    } else {
+                        button("Hide").on_press(GUIMessage::HidePassword)
+                    };
+                    content = content.push(spec_input(&self.value)).push(row![
+                        password_generate,
+                        passphrase_generate,
+                        toggle_show
+                    ]);
+                }
+                container(content)
+            }
         };
         let create_button = button("Create").on_press(GUIMessage::Submit);
         let cancel_button = button("Cancel").on_press(GUIMessage::Exit);
@@ -133,7 +196,7 @@ impl NewEntryState {
             header,
             container(column![
                 row![name_prefix, name_input],
-                style_choice,
+                row![style_choice, template_choice],
                 data_input,
                 row![create_button, cancel_button]
             ]),
@@ -44,7 +44,7 @@ impl PasswordState {
             true
         }
     }
-    pub fn view(&self) -> Element<GUIMessage> {
+    pub fn view(&self) -> Element<'_, GUIMessage> {
         let header = text("Vault password");
         let password_input = text_input("vault password", self.password.clone().expose_secret())
             .id(INPUT_ID.clone())
@@ -11,7 +11,7 @@ pub struct PromptState {
 }
 
 impl PromptState {
-    pub fn view(&self) -> Element<GUIMessage> {
+    pub fn view(&self) -> Element<'_, GUIMessage> {
         let header = text("New vault name");
         let name_input = text_input("Name", &self.vault)
             .id(INPUT_ID.clone())
@@ -5,13 +5,11 @@ use iced::{
 use secrecy::ExposeSecret;
 
 use pants_store::{
-    manager_message::ManagerMessage,
-    message::Message,
     store::{StoreChoice, StoreHash},
     Password,
 };
 
-use super::gui_message::GUIMessage;
+use super::{connection::Request, gui_message::GUIMessage};
 
 // first field is vault name
 #[derive(Debug, Clone, Default)]
@@ -69,30 +67,25 @@ impl TempMessage {
         }
     }
 
-    pub fn with_password(&self, password: Password) -> ManagerMessage {
+    pub fn with_password(&self, password: Password) -> Request {
         match self {
-            Self::Delete(vault, key) => ManagerMessage::VaultMessage(
-                vault.into(),
-                Message::Delete(password, key.to_string()),
-            ),
-            Self::Get(vault, key) => {
-                ManagerMessage::VaultMessage(vault.into(), Message::Get(password, key.to_string()))
+            Self::Delete(vault, key) => Request::Delete(vault.into(), key.into(), password),
+            Self::Get(vault, key) => Request::Get(vault.into(), key.into(), password),
+            Self::New(vault, key, choice, value) | Self::Update(vault, key, choice, value) => {
+                Request::Update(
+                    vault.into(),
+                    key.into(),
+                    password,
+                    choice.convert(value).unwrap(),
+                )
             }
-            Self::New(vault, key, choice, value) => ManagerMessage::VaultMessage(
-                vault.into(),
-                Message::Update(password, key.clone(), choice.convert(value).unwrap()),
-            ),
-            Self::Update(vault, key, choice, value) => ManagerMessage::VaultMessage(
-                vault.into(),
-                Message::Update(password, key.clone(), choice.convert(value).unwrap()),
-            ),
-            Self::DeleteVault(vault) => ManagerMessage::DeleteVault(vault.into(), password),
-            Self::DeleteEmptyVault(vault) => ManagerMessage::DeleteEmptyVault(vault.into()),
-            Self::Empty => ManagerMessage::Info,
+            Self::DeleteVault(vault) => Request::DeleteVault(vault.into(), password),
+            Self::DeleteEmptyVault(vault) => Request::DeleteEmptyVault(vault.into()),
+            Self::Empty => Request::Info,
         }
     }
 
-    pub fn view(&self) -> Element<GUIMessage> {
+    pub fn view(&self) -> Element<'_, GUIMessage> {
         match self {
             TempMessage::Delete(vault, key) => {
                 let info = text(format!("Working on deleting {} in {}", key, vault));
@@ -1,6 +1,7 @@
 use std::collections::BTreeMap;
 
 use boring_derive::Builder;
+use chrono::Utc;
 use iced::{
     alignment, theme,
     widget::{button, column, container, row, text, tooltip},
@@ -43,19 +44,42 @@ impl Vault {
     }
 
     pub fn update(&mut self, schema: &Schema) {
+        let now = Utc::now();
         self.entries = schema
             .data
-            .iter()
-            .map(|(key, value)| {
+            .keys()
+            .map(|key| {
                 (
                     key.to_string(),
-                    Entry::new(key.to_string(), value.to_string()),
+                    Entry::new(
+                        key.to_string(),
+                        schema.tags(key),
+                        schema.folder(key).map(|folder| folder.to_string()),
+                        schema
+                            .public
+                            .get(key)
+                            .map(|values| values.values().cloned().collect())
+                            .unwrap_or_default(),
+                        schema.age_status(key, now),
+                    ),
                 )
             })
             .collect();
     }
 
-    pub fn view(&self) -> Element<VaultMessage> {
+    /// whether any entry matches every term of the filter
+    pub fn matches(&self, filter: &str) -> bool {
+        self.entries
+            .values()
+            .any(|e| Self::entry_matches(e, filter))
+    }
+
+    fn entry_matches(entry: &Entry, filter: &str) -> bool {
+        filter.split_whitespace().all(|term| entry.matches(term))
+    }
+
+    /// entries matching the filter grouped under their folders, an empty filter shows everything
+    pub fn view(&self, filter: &str) -> Element<'_, VaultMessage> {
         let name = text(self.name.to_string()).size(20).width(Length::Fill);
         let delete_button = tooltip(
             button("X")
@@ -73,14 +97,31 @@ impl Vault {
         // .font(Font::MONOSPACE)
         // .width(Length::Shrink);
         let header = row![name, delete_button];
-        let mut entries = self
+        let mut folders: BTreeMap<Option<&str>, Vec<&Entry>> = BTreeMap::new();
+        for e in self
             .entries
             .values()
-            .map(|e| {
+            .filter(|e| Self::entry_matches(e, filter))
+        {
+            folders.entry(e.folder.as_deref()).or_default().push(e);
+        }
+        let mut entries = vec![];
+        for (folder, group) in folders {
+            let group = group.into_iter().map(|e| {
                 e.view()
                     .map(move |message| VaultMessage::Entry(message, e.key.clone()))
-            })
-            .collect::<Vec<_>>();
+            });
+            match folder {
+                Some(folder) => entries.push(
+                    column![
+                        text(format!("{folder}/")),
+                        container(column(group)).padding([0, 0, 0, 15])
+                    ]
+                    .into(),
+                ),
+                None => entries.extend(group),
+            }
+        }
         entries.push(
             container(
                 button(
@@ -98,7 +139,7 @@ impl Vault {
         );
         let content = container(column(entries)).padding(10);
 
-        Expand::new(header, content, self.expanded)
+        Expand::new(header, content, self.expanded || !filter.is_empty())
             .on_press(VaultMessage::Toggle)
             .into()
     }
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::store::Store;
+use crate::{migration, secure::Versioned, store::Store};
 
+// records hold few enough actions that boxing the stores isn't worth it
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, Serialize, Deserialize)]
 pub enum Action {
     // Read {
@@ -46,6 +48,10 @@ impl IntoIterator for Record {
     }
 }
 
+impl Versioned for Record {
+    type Legacy = migration::v1::Record;
+}
+
 impl Default for Record {
     fn default() -> Self {
         Self::new()
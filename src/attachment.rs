@@ -0,0 +1,214 @@
+//! binary files attached to entries, each one is encrypted under the vault key and kept in its
+//! own file so opening the vault doesn't have to decrypt them
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{Debug, Display},
+};
+
+use rand::{rngs::OsRng, RngCore};
+use secrecy::{ExposeSecret, SecretVec};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_bytes::ByteBuf;
+
+use crate::{errors::AttachmentError, secure::Versioned, store::Store};
+
+/// contents of an attached file, zeroed when dropped
+pub struct Attachment(SecretVec<u8>);
+
+impl Attachment {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self(SecretVec::new(data))
+    }
+
+    pub fn size(&self) -> u64 {
+        self.0.expose_secret().len() as u64
+    }
+}
+
+impl ExposeSecret<Vec<u8>> for Attachment {
+    fn expose_secret(&self) -> &Vec<u8> {
+        self.0.expose_secret()
+    }
+}
+
+impl Debug for Attachment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Attachment({} bytes)", self.size())
+    }
+}
+
+impl Serialize for Attachment {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde_bytes::serialize(self.0.expose_secret(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Attachment {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ByteBuf::deserialize(deserializer).map(|bytes| Self::new(bytes.into_vec()))
+    }
+}
+
+impl Versioned for Attachment {
+    type Legacy = Self;
+}
+
+/// where an attachment is stored and how big it is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentInfo {
+    /// name of the file holding the attachment, unrelated to the attachment's name
+    pub id: String,
+    pub size: u64,
+}
+
+impl AttachmentInfo {
+    pub fn new(size: u64) -> Self {
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+        let id = bytes.iter().map(|b| format!("{b:02x}")).collect();
+        Self { id, size }
+    }
+}
+
+impl Display for AttachmentInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.size {
+            size if size >= 1 << 20 => write!(f, "{:.1} MiB", size as f64 / (1 << 20) as f64),
+            size if size >= 1 << 10 => write!(f, "{:.1} KiB", size as f64 / (1 << 10) as f64),
+            size => write!(f, "{size} B"),
+        }
+    }
+}
+
+/// attachments of every entry, entry -> attachment name -> info
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttachmentIndex {
+    pub data: BTreeMap<String, BTreeMap<String, AttachmentInfo>>,
+}
+
+impl Versioned for AttachmentIndex {
+    type Legacy = Self;
+}
+
+impl AttachmentIndex {
+    pub fn get(&self, key: &str, name: &str) -> Option<&AttachmentInfo> {
+        self.data.get(key)?.get(name)
+    }
+
+    /// attachments of an entry
+    pub fn entry(&self, key: &str) -> BTreeMap<String, AttachmentInfo> {
+        self.data.get(key).cloned().unwrap_or_default()
+    }
+
+    pub fn insert(
+        &mut self,
+        key: &str,
+        name: &str,
+        info: AttachmentInfo,
+    ) -> Option<AttachmentInfo> {
+        self.data
+            .entry(key.into())
+            .or_default()
+            .insert(name.into(), info)
+    }
+
+    pub fn remove(&mut self, key: &str, name: &str) -> Option<AttachmentInfo> {
+        let attachments = self.data.get_mut(key)?;
+        let removed = attachments.remove(name);
+        if attachments.is_empty() {
+            self.data.remove(key);
+        }
+        removed
+    }
+
+    pub fn remove_entry(&mut self, key: &str) -> BTreeMap<String, AttachmentInfo> {
+        self.data.remove(key).unwrap_or_default()
+    }
+
+    pub fn rename_entry(&mut self, from: &str, to: &str) {
+        if let Some(attachments) = self.data.remove(from) {
+            self.data.insert(to.into(), attachments);
+        }
+    }
+
+    /// every attachment in the vault
+    pub fn all(&self) -> impl Iterator<Item = &AttachmentInfo> {
+        self.data
+            .values()
+            .flat_map(|attachments| attachments.values())
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.all().map(|info| info.size).sum()
+    }
+}
+
+/// how large attachments are allowed to get, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachmentLimits {
+    /// size of a single attachment
+    pub size: u64,
+    /// size of all the attachments in a vault
+    pub total: u64,
+}
+
+impl Default for AttachmentLimits {
+    fn default() -> Self {
+        Self {
+            size: 10 << 20,
+            total: 100 << 20,
+        }
+    }
+}
+
+impl AttachmentLimits {
+    /// check that an attachment of `size` fits next to the ones in the index
+    pub fn check(&self, index: &AttachmentIndex, size: u64) -> Result<(), AttachmentError> {
+        if size > self.size {
+            Err(AttachmentError::TooLarge(self.size))
+        } else if index.total_size() + size > self.total {
+            Err(AttachmentError::VaultFull(self.total))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// contents of a vault as exported, entries along with their attachments
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(from = "ExportFormat")]
+pub struct Export {
+    pub entries: BTreeMap<String, Store>,
+    pub attachments: BTreeMap<String, BTreeMap<String, Attachment>>,
+}
+
+/// exports written before attachments existed are only the map of entries
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExportFormat {
+    Full {
+        entries: BTreeMap<String, Store>,
+        #[serde(default)]
+        attachments: BTreeMap<String, BTreeMap<String, Attachment>>,
+    },
+    Entries(HashMap<String, Store>),
+}
+
+impl From<ExportFormat> for Export {
+    fn from(value: ExportFormat) -> Self {
+        match value {
+            ExportFormat::Full {
+                entries,
+                attachments,
+            } => Self {
+                entries,
+                attachments,
+            },
+            ExportFormat::Entries(entries) => Self {
+                entries: entries.into_iter().collect(),
+                attachments: BTreeMap::new(),
+            },
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! health report of the passwords stored in vaults: reuse, weakness, spec violations and age
+
+use std::{collections::HashMap, fmt::Display, path::PathBuf, str::FromStr};
+
+use chrono::Utc;
+use pants_gen::{charset::Charset, interval::Interval, password::PasswordSpec};
+use secrecy::ExposeSecret;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    breach::BreachList,
+    policy::{AgePolicy, AgeStatus},
+    store::FieldKind,
+    strength::Strength,
+    vault::Vault,
+};
+
+/// what to check the passwords against
+#[derive(Debug, Clone)]
+pub struct AuditSettings {
+    /// spec passwords should satisfy when their field doesn't remember the one it was generated
+    /// with, skipped when neither is there
+    pub spec: Option<PasswordSpec>,
+    /// days a password may go unchanged in vaults without an age policy
+    pub max_age: u32,
+    /// local copy of the Have I Been Pwned list to look the passwords up in, see [BreachList]
+    pub breached: Option<PathBuf>,
+}
+
+impl Default for AuditSettings {
+    fn default() -> Self {
+        Self {
+            spec: None,
+            max_age: 365,
+            breached: None,
+        }
+    }
+}
+
+/// a password field of an entry
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Location {
+    pub vault: String,
+    pub key: String,
+    pub field: String,
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}-{}", self.vault, self.key, self.field)
+    }
+}
+
+/// a problem with a password
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "issue", rename_all = "snake_case")]
+pub enum Issue {
+    /// the same password is stored in the other places
+    Reused {
+        with: Vec<Location>,
+    },
+    Weak {
+        strength: Strength,
+    },
+    /// doesn't satisfy the spec
+    Spec {
+        spec: String,
+    },
+    Stale {
+        status: AgeStatus,
+    },
+    /// found in the breach list this many times
+    Breached {
+        count: u64,
+    },
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Reused { with } => {
+                let with: Vec<String> = with.iter().map(|l| l.to_string()).collect();
+                write!(f, "reused in {}", with.join(", "))
+            }
+            Self::Weak { strength } => write!(f, "weak, about {strength}"),
+            Self::Spec { spec } => write!(f, "doesn't satisfy the spec `{spec}`"),
+            Self::Stale { status } => write!(f, "unchanged for too long, {status}"),
+            Self::Breached { count } => write!(f, "found in breaches {count} times"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    #[serde(flatten)]
+    pub location: Location,
+    #[serde(flatten)]
+    pub issue: Issue,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.issue)
+    }
+}
+
+/// problems found in the audited vaults, never containing the passwords themselves
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Audit {
+    pub vaults: Vec<String>,
+    pub findings: Vec<Finding>,
+}
+
+impl Display for Audit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.findings.is_empty() {
+            return writeln!(f, "No problems found in {}", self.vaults.join(", "));
+        }
+        for finding in &self.findings {
+            writeln!(f, "{finding}")?;
+        }
+        Ok(())
+    }
+}
+
+/// collects findings over vaults, reuse is only reported once every vault has been checked
+pub struct Auditor {
+    settings: AuditSettings,
+    breaches: Option<BreachList>,
+    /// locations of each password by its hash
+    seen: HashMap<Vec<u8>, Vec<Location>>,
+    audit: Audit,
+}
+
+impl Auditor {
+    pub fn new(settings: AuditSettings) -> anyhow::Result<Self> {
+        let breaches = match &settings.breached {
+            Some(path) => Some(BreachList::open(path)?),
+            None => None,
+        };
+        Ok(Self {
+            settings,
+            breaches,
+            seen: HashMap::new(),
+            audit: Audit::default(),
+        })
+    }
+
+    /// check the password fields of the vault's entries
+    pub fn check(&mut self, name: &str, vault: &Vault, policy: &AgePolicy) -> anyhow::Result<()> {
+        let now = Utc::now();
+        let policy = if policy.is_empty() {
+            AgePolicy {
+                max_age: Some(self.settings.max_age),
+                ..Default::default()
+            }
+        } else {
+            policy.clone()
+        };
+        self.audit.vaults.push(name.into());
+        for (key, store) in vault.entries() {
+            for (field, value) in &store.data {
+                if store.kind(field) != FieldKind::Password {
+                    continue;
+                }
+                let value = value.expose_secret().to_string();
+                if value.is_empty() {
+                    continue;
+                }
+                let location = Location {
+                    vault: name.into(),
+                    key: key.clone(),
+                    field: field.clone(),
+                };
+                self.seen
+                    .entry(Sha256::digest(&value).to_vec())
+                    .or_default()
+                    .push(location.clone());
+                let strength = Strength::estimate(&value);
+                if strength.is_weak() {
+                    self.push(&location, Issue::Weak { strength });
+                }
+                // the spec the field was generated with, the configured one otherwise
+                let spec = store
+                    .spec(field)
+                    .and_then(|spec| PasswordSpec::from_str(spec).ok())
+                    .or_else(|| self.settings.spec.clone());
+                if let Some(spec) = &spec {
+                    if !satisfies(spec, &value) {
+                        let spec = spec.to_string();
+                        self.push(&location, Issue::Spec { spec });
+                    }
+                }
+                if let Some(status @ AgeStatus::Overdue(_)) =
+                    policy.status(key, store.modified, now)
+                {
+                    self.push(&location, Issue::Stale { status });
+                }
+                if let Some(breaches) = &mut self.breaches {
+                    if let Some(count) = breaches.lookup(&value)? {
+                        self.push(&location, Issue::Breached { count });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, location: &Location, issue: Issue) {
+        self.audit.findings.push(Finding {
+            location: location.clone(),
+            issue,
+        });
+    }
+
+    /// the findings, with the reused passwords
+    pub fn finish(mut self) -> Audit {
+        for locations in self.seen.into_values() {
+            if locations.len() < 2 {
+                continue;
+            }
+            for location in &locations {
+                let with = locations
+                    .iter()
+                    .filter(|other| *other != location)
+                    .cloned()
+                    .collect();
+                self.audit.findings.push(Finding {
+                    location: location.clone(),
+                    issue: Issue::Reused { with },
+                });
+            }
+        }
+        self.audit
+            .findings
+            .sort_by(|a, b| a.location.cmp(&b.location));
+        self.audit
+    }
+}
+
+/// whether the password is at least as long as the spec and has as many characters of each of
+/// its character sets as it asks for
+pub fn satisfies(spec: &PasswordSpec, password: &str) -> bool {
+    // the spec's parts aren't public, its string form is the way to get at them
+    let spec = spec.to_string();
+    let mut segments = spec.split("//");
+    let length: usize = segments
+        .next()
+        .and_then(|length| length.parse().ok())
+        .unwrap_or_default();
+    if password.chars().count() < length {
+        return false;
+    }
+    segments
+        .filter_map(|segment| {
+            let (interval, chars) = segment.split_once('|')?;
+            Some((interval.parse::<Interval>().ok()?, chars.parse().ok()?))
+        })
+        .all(|(interval, charset): (Interval, Charset)| {
+            let count = password
+                .chars()
+                .filter(|c| match &charset {
+                    Charset::Upper => c.is_ascii_uppercase(),
+                    Charset::Lower => c.is_ascii_lowercase(),
+                    Charset::Number => c.is_ascii_digit(),
+                    charset => charset.to_charset().contains(c),
+                })
+                .count();
+            interval.min <= count && count <= interval.max
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use pants_gen::password::PasswordSpec;
+
+    use super::{AuditSettings, Auditor, Issue};
+    use crate::{
+        command::Command,
+        policy::AgePolicy,
+        store::{Store, StoredValue},
+        vault::Vault,
+    };
+
+    fn spec(length: usize) -> PasswordSpec {
+        PasswordSpec::new().length(length).lower_at_least(1)
+    }
+
+    /// the specs the password of an entry with the given remembered spec was flagged for
+    fn spec_issues(stored: Option<usize>, configured: Option<usize>) -> Vec<String> {
+        let mut store = Store::password(StoredValue::new("correct-horse-battery-staple"));
+        store.set_spec("Password", stored.map(|length| spec(length).to_string()));
+        let mut vault = Vault::new();
+        let (_, record) = vault.transaction(
+            Command::Update {
+                key: "a".into(),
+                value: store,
+            }
+            .into(),
+        );
+        vault.apply_record(record);
+
+        let settings = AuditSettings {
+            spec: configured.map(spec),
+            ..Default::default()
+        };
+        let mut auditor = Auditor::new(settings).unwrap();
+        auditor
+            .check("vault", &vault, &AgePolicy::default())
+            .unwrap();
+        auditor
+            .finish()
+            .findings
+            .into_iter()
+            .filter_map(|finding| match finding.issue {
+                Issue::Spec { spec } => Some(spec),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn remembered_spec_is_checked() {
+        assert_eq!(spec_issues(Some(40), None).len(), 1);
+    }
+
+    #[test]
+    fn remembered_spec_takes_precedence() {
+        assert_eq!(spec_issues(Some(40), Some(8)).len(), 1);
+        assert!(spec_issues(Some(28), Some(64)).is_empty());
+    }
+
+    #[test]
+    fn configured_spec_without_remembered_one() {
+        assert_eq!(spec_issues(None, Some(64)).len(), 1);
+        assert!(spec_issues(None, Some(8)).is_empty());
+        assert!(spec_issues(None, None).is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+//! lookups in a local copy of the Have I Been Pwned password list, nothing is sent anywhere
+//!
+//! The list is the SHA-1 version ordered by hash, one `HASH:COUNT` line per password, so a
+//! password is found with a binary search over the file instead of reading all of it.
+
+use std::{
+    cmp::Ordering,
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::Path,
+};
+
+use sha1::{Digest, Sha1};
+
+/// an open breach list
+pub struct BreachList {
+    reader: BufReader<File>,
+    len: u64,
+}
+
+impl BreachList {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            reader: BufReader::new(file),
+            len,
+        })
+    }
+
+    /// how many times the password shows up in breaches, [None] when it isn't in the list
+    pub fn lookup(&mut self, password: &str) -> std::io::Result<Option<u64>> {
+        let hash: String = Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect();
+        let mut low = 0;
+        let mut high = self.len;
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let Some((start, end, line)) = self.line_from(middle)? else {
+                high = middle;
+                continue;
+            };
+            if start >= high {
+                high = middle;
+                continue;
+            }
+            let (line_hash, count) = line.split_once(':').unwrap_or((&line, ""));
+            match line_hash.to_ascii_uppercase().as_str().cmp(&hash) {
+                Ordering::Equal => return Ok(Some(count.trim().parse().unwrap_or(1))),
+                Ordering::Less => low = end,
+                Ordering::Greater => high = middle,
+            }
+        }
+        Ok(None)
+    }
+
+    /// the first line starting at or after `position`, with where it starts and ends
+    fn line_from(&mut self, position: u64) -> std::io::Result<Option<(u64, u64, String)>> {
+        let mut start = position;
+        if position > 0 {
+            // the line before might end right before `position`
+            self.reader.seek(SeekFrom::Start(position - 1))?;
+            let mut skipped = vec![];
+            start = position - 1 + self.reader.read_until(b'\n', &mut skipped)? as u64;
+        } else {
+            self.reader.seek(SeekFrom::Start(0))?;
+        }
+        let mut line = String::new();
+        let read = self.reader.read_line(&mut line)? as u64;
+        if read == 0 {
+            return Ok(None);
+        }
+        Ok(Some((start, start + read, line.trim_end().to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use sha1::{Digest, Sha1};
+
+    use super::BreachList;
+
+    fn hash(password: &str) -> String {
+        Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect()
+    }
+
+    /// a list of `pw0` to `pw99`, each seen one more time than its number, ordered by hash
+    fn list(line_end: &str) -> (tempfile::NamedTempFile, Vec<(String, String)>) {
+        let mut passwords: Vec<(String, String)> = (0..100)
+            .map(|i| (hash(&format!("pw{i}")), format!("pw{i}")))
+            .collect();
+        passwords.sort();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for (hash, password) in &passwords {
+            let count = password[2..].parse::<u64>().unwrap() + 1;
+            write!(file, "{hash}:{count}{line_end}").unwrap();
+        }
+        (file, passwords)
+    }
+
+    fn check(line_end: &str) {
+        let (file, passwords) = list(line_end);
+        let mut breaches = BreachList::open(file.path()).unwrap();
+        for (_, password) in [&passwords[0], &passwords[50], &passwords[99]] {
+            let count = password[2..].parse::<u64>().unwrap() + 1;
+            assert_eq!(
+                breaches.lookup(password).unwrap(),
+                Some(count),
+                "{password}"
+            );
+        }
+        for (_, password) in &passwords {
+            assert!(breaches.lookup(password).unwrap().is_some(), "{password}");
+        }
+        assert_eq!(breaches.lookup("pw100").unwrap(), None);
+        assert_eq!(breaches.lookup("").unwrap(), None);
+    }
+
+    #[test]
+    fn lookup() {
+        check("\n");
+    }
+
+    #[test]
+    fn lookup_crlf() {
+        check("\r\n");
+    }
+
+    #[test]
+    fn lookup_single_line() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "{}:3", hash("password")).unwrap();
+        let mut breaches = BreachList::open(file.path()).unwrap();
+        assert_eq!(breaches.lookup("password").unwrap(), Some(3));
+        assert_eq!(breaches.lookup("other").unwrap(), None);
+    }
+}
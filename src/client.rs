@@ -0,0 +1,501 @@
+//! typed calls into the vaults, so callers don't have to build [ManagerMessage]s and match the
+//! [Output] they get back
+
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use pants_gen::password::PasswordSpec;
+use secrecy::ExposeSecret;
+
+use crate::{
+    attachment::{Attachment, AttachmentInfo, Export},
+    audit::{Audit, AuditSettings},
+    doctor::Problem,
+    errors::CommunicationError,
+    file::BackupFile,
+    info::Info,
+    manager_message::ManagerMessage,
+    message::{BatchStep, Message},
+    output::Output,
+    reads::Reads,
+    rotation::{EntryFilter, Rotation},
+    schema::Schema,
+    store::{Changes, FieldKind, Store},
+    totp::{Code, Totp},
+    url::{Site, UrlMatch},
+    vault::{interface::VaultSession, manager::VaultManager},
+    Password,
+};
+
+/// the value of the output when it's the expected variant
+macro_rules! expect {
+    ($output:expr, $variant:path) => {
+        match $output {
+            $variant(value) => Ok(value),
+            _ => Err(anyhow::Error::from(CommunicationError::UnexpectedOutput)),
+        }
+    };
+}
+
+/// a client of the vaults, wrapping the [VaultManager]
+#[derive(Default)]
+pub struct PantsClient {
+    manager: VaultManager,
+}
+
+impl From<VaultManager> for PantsClient {
+    fn from(manager: VaultManager) -> Self {
+        Self { manager }
+    }
+}
+
+impl PantsClient {
+    pub fn new() -> anyhow::Result<Self> {
+        VaultManager::new().map(Self::from)
+    }
+
+    fn send(&mut self, message: ManagerMessage) -> anyhow::Result<Output> {
+        self.manager.receive(message)
+    }
+
+    fn send_vault(&mut self, vault: &str, message: Message) -> anyhow::Result<Output> {
+        self.send(ManagerMessage::VaultMessage(vault.into(), message))
+    }
+
+    /// the vault a client means when it doesn't name one
+    pub fn default_vault(&self) -> String {
+        self.manager.default_vault()
+    }
+
+    /// the vault name an alias refers to, or the name itself when it isn't an alias
+    pub fn resolve(&self, name: &str) -> String {
+        self.manager.resolve(name)
+    }
+
+    /// names of the registered vaults
+    pub fn vaults(&mut self) -> anyhow::Result<Vec<String>> {
+        expect!(self.send(ManagerMessage::List)?, Output::List)
+    }
+
+    /// the schemas of every vault
+    pub fn info(&mut self) -> anyhow::Result<Info> {
+        expect!(self.send(ManagerMessage::Info)?, Output::Info)
+    }
+
+    pub fn new_vault(&mut self, name: &str) -> anyhow::Result<()> {
+        self.send(ManagerMessage::NewVault(name.into()))?;
+        Ok(())
+    }
+
+    pub fn delete_vault(&mut self, name: &str, password: Password) -> anyhow::Result<()> {
+        self.send(ManagerMessage::DeleteVault(name.into(), password))?;
+        Ok(())
+    }
+
+    /// delete a vault without entries, which needs no password
+    pub fn delete_empty_vault(&mut self, name: &str) -> anyhow::Result<()> {
+        self.send(ManagerMessage::DeleteEmptyVault(name.into()))?;
+        Ok(())
+    }
+
+    pub fn rename_vault(&mut self, from: &str, to: &str) -> anyhow::Result<()> {
+        self.send(ManagerMessage::RenameVault(from.into(), to.into()))?;
+        Ok(())
+    }
+
+    pub fn add_alias(&mut self, alias: &str, vault: &str) -> anyhow::Result<()> {
+        self.send(ManagerMessage::AddAlias(alias.into(), vault.into()))?;
+        Ok(())
+    }
+
+    pub fn remove_alias(&mut self, alias: &str) -> anyhow::Result<()> {
+        self.send(ManagerMessage::RemoveAlias(alias.into()))?;
+        Ok(())
+    }
+
+    pub fn set_default_vault(&mut self, vault: &str) -> anyhow::Result<()> {
+        self.send(ManagerMessage::SetDefaultVault(vault.into()))?;
+        Ok(())
+    }
+
+    /// whether the vault keeps its tags, folders and public values out of the schema
+    pub fn hide_metadata(&mut self, vault: &str, hide: bool) -> anyhow::Result<()> {
+        self.send(ManagerMessage::HideMetadata(vault.into(), hide))?;
+        Ok(())
+    }
+
+    /// whether the vault writes the values of its public fields to the schema
+    pub fn publish_fields(&mut self, vault: &str, publish: bool) -> anyhow::Result<()> {
+        self.send(ManagerMessage::PublishFields(vault.into(), publish))?;
+        Ok(())
+    }
+
+    /// move the entries between vaults, returning the backups made of both vaults first
+    pub fn move_entries(
+        &mut self,
+        from: &str,
+        from_password: Password,
+        to: &str,
+        to_password: Password,
+        keys: Vec<String>,
+    ) -> anyhow::Result<Vec<BackupFile>> {
+        expect!(
+            self.send(ManagerMessage::MoveEntries(
+                from.into(),
+                from_password,
+                to.into(),
+                to_password,
+                keys
+            ))?,
+            Output::BackupFiles
+        )
+    }
+
+    /// copy the entries between vaults, see [Self::move_entries]
+    pub fn copy_entries(
+        &mut self,
+        from: &str,
+        from_password: Password,
+        to: &str,
+        to_password: Password,
+        keys: Vec<String>,
+    ) -> anyhow::Result<Vec<BackupFile>> {
+        expect!(
+            self.send(ManagerMessage::CopyEntries(
+                from.into(),
+                from_password,
+                to.into(),
+                to_password,
+                keys
+            ))?,
+            Output::BackupFiles
+        )
+    }
+
+    /// the schema of the vault, without the tags, folders and public values when it hides them
+    pub fn schema(&mut self, vault: &str) -> anyhow::Result<Schema> {
+        expect!(self.send_vault(vault, Message::Schema)?, Output::Schema)
+    }
+
+    /// the schema of the vault including what it hides
+    pub fn metadata(&mut self, vault: &str, password: Password) -> anyhow::Result<Schema> {
+        expect!(
+            self.send_vault(vault, Message::Metadata(password))?,
+            Output::Schema
+        )
+    }
+
+    pub fn get(&mut self, vault: &str, key: &str, password: Password) -> anyhow::Result<Store> {
+        let mut reads = expect!(
+            self.send_vault(vault, Message::Get(password, key.into()))?,
+            Output::Read
+        )?;
+        Ok(reads.remove(key).ok_or(CommunicationError::NoEntry)?)
+    }
+
+    /// store the entry, replacing any entry with the same name
+    pub fn update(
+        &mut self,
+        vault: &str,
+        key: &str,
+        password: Password,
+        value: Store,
+    ) -> anyhow::Result<()> {
+        self.send_vault(vault, Message::Update(password, key.into(), value))?;
+        Ok(())
+    }
+
+    /// apply the changes to the entry, returning its new value
+    pub fn change(
+        &mut self,
+        vault: &str,
+        key: &str,
+        password: Password,
+        changes: Changes,
+    ) -> anyhow::Result<Store> {
+        let mut reads = expect!(
+            self.send_vault(vault, Message::Change(password, key.into(), changes))?,
+            Output::Read
+        )?;
+        Ok(reads.remove(key).ok_or(CommunicationError::NoEntry)?)
+    }
+
+    pub fn rename(
+        &mut self,
+        vault: &str,
+        from: &str,
+        to: &str,
+        password: Password,
+    ) -> anyhow::Result<()> {
+        self.send_vault(vault, Message::Rename(password, from.into(), to.into()))?;
+        Ok(())
+    }
+
+    pub fn delete(&mut self, vault: &str, key: &str, password: Password) -> anyhow::Result<()> {
+        self.send_vault(vault, Message::Delete(password, key.into()))?;
+        Ok(())
+    }
+
+    /// the current codes of the entry's TOTP fields by field name
+    pub fn totp(
+        &mut self,
+        vault: &str,
+        key: &str,
+        password: Password,
+    ) -> anyhow::Result<Vec<(String, Code)>> {
+        let store = self.get(vault, key, password)?;
+        store
+            .data
+            .iter()
+            .filter(|(field, _)| store.kind(field) == FieldKind::Totp)
+            .map(|(field, value)| {
+                let totp: Totp = value.expose_secret().to_string().parse()?;
+                Ok((field.clone(), totp.code()))
+            })
+            .collect()
+    }
+
+    pub fn tag(
+        &mut self,
+        vault: &str,
+        key: &str,
+        password: Password,
+        add: Vec<String>,
+        remove: Vec<String>,
+    ) -> anyhow::Result<()> {
+        self.send_vault(vault, Message::Tag(password, key.into(), add, remove))?;
+        Ok(())
+    }
+
+    /// move the entry into the folder, an empty path moves it out of any folder
+    pub fn folder(
+        &mut self,
+        vault: &str,
+        key: &str,
+        password: Password,
+        path: &str,
+    ) -> anyhow::Result<()> {
+        self.send_vault(vault, Message::Folder(password, key.into(), path.into()))?;
+        Ok(())
+    }
+
+    /// run the steps as one transaction, see [Message::Batch]
+    pub fn batch(
+        &mut self,
+        vault: &str,
+        password: Password,
+        steps: Vec<BatchStep>,
+    ) -> anyhow::Result<Reads<Store>> {
+        expect!(
+            self.send_vault(vault, Message::Batch(password, steps))?,
+            Output::Read
+        )
+    }
+
+    /// entries and fields whose values contain the query, see [Message::Search]
+    pub fn search(
+        &mut self,
+        vault: &str,
+        password: Password,
+        query: &str,
+        include_secrets: bool,
+    ) -> anyhow::Result<Schema> {
+        expect!(
+            self.send_vault(
+                vault,
+                Message::Search(password, query.into(), include_secrets)
+            )?,
+            Output::Schema
+        )
+    }
+
+    /// the vault's entries and attachments as JSON, see [Export]
+    pub fn export(&mut self, vault: &str, password: Password) -> anyhow::Result<String> {
+        expect!(
+            self.send_vault(vault, Message::Export(password))?,
+            Output::Content
+        )
+    }
+
+    pub fn import(&mut self, vault: &str, password: Password, data: Export) -> anyhow::Result<()> {
+        self.send_vault(vault, Message::Import(password, data))?;
+        Ok(())
+    }
+
+    /// change the vault's password, returning the backup made of it first
+    pub fn rotate(
+        &mut self,
+        vault: &str,
+        password: Password,
+        new_password: Password,
+    ) -> anyhow::Result<BackupFile> {
+        expect!(
+            self.send_vault(vault, Message::Rotate(password, new_password))?,
+            Output::Backup
+        )
+    }
+
+    /// regenerate a field, or the password fields, of the matching entries, see
+    /// [Message::RotateEntries]
+    pub fn rotate_entries(
+        &mut self,
+        vault: &str,
+        password: Password,
+        filter: EntryFilter,
+        field: Option<String>,
+        default: PasswordSpec,
+    ) -> anyhow::Result<Rotation> {
+        expect!(
+            self.send_vault(
+                vault,
+                Message::RotateEntries(password, filter, field, default)
+            )?,
+            Output::Rotation
+        )
+    }
+
+    pub fn backup(&mut self, vault: &str, password: Password) -> anyhow::Result<BackupFile> {
+        expect!(
+            self.send_vault(vault, Message::Backup(password))?,
+            Output::Backup
+        )
+    }
+
+    pub fn backups(&mut self, vault: &str) -> anyhow::Result<Vec<BackupFile>> {
+        expect!(
+            self.send_vault(vault, Message::BackupList)?,
+            Output::BackupFiles
+        )
+    }
+
+    /// copy in the backup, returning the backup made of the vault before it
+    pub fn restore(
+        &mut self,
+        vault: &str,
+        password: Password,
+        backup_password: Password,
+        backup: BackupFile,
+    ) -> anyhow::Result<BackupFile> {
+        expect!(
+            self.send_vault(vault, Message::Restore(password, backup_password, backup))?,
+            Output::Backup
+        )
+    }
+
+    /// remove all but the `keep` most recent backups, returning the removed ones
+    pub fn prune_backups(&mut self, vault: &str, keep: usize) -> anyhow::Result<Vec<BackupFile>> {
+        expect!(
+            self.send_vault(vault, Message::PruneBackups(keep))?,
+            Output::BackupFiles
+        )
+    }
+
+    pub fn attachments(
+        &mut self,
+        vault: &str,
+        key: &str,
+        password: Password,
+    ) -> anyhow::Result<BTreeMap<String, AttachmentInfo>> {
+        expect!(
+            self.send_vault(vault, Message::ListAttachments(password, key.into()))?,
+            Output::Attachments
+        )
+    }
+
+    pub fn attachment(
+        &mut self,
+        vault: &str,
+        key: &str,
+        name: &str,
+        password: Password,
+    ) -> anyhow::Result<Arc<Attachment>> {
+        expect!(
+            self.send_vault(
+                vault,
+                Message::GetAttachment(password, key.into(), name.into())
+            )?,
+            Output::Attachment
+        )
+    }
+
+    /// add an attachment to the entry, replacing one with the same name
+    pub fn attach(
+        &mut self,
+        vault: &str,
+        key: &str,
+        name: &str,
+        password: Password,
+        attachment: Attachment,
+    ) -> anyhow::Result<()> {
+        self.send_vault(
+            vault,
+            Message::Attach(password, key.into(), name.into(), attachment),
+        )?;
+        Ok(())
+    }
+
+    pub fn detach(
+        &mut self,
+        vault: &str,
+        key: &str,
+        name: &str,
+        password: Password,
+    ) -> anyhow::Result<()> {
+        self.send_vault(vault, Message::Detach(password, key.into(), name.into()))?;
+        Ok(())
+    }
+
+    /// entries of every vault with a URL field for the site, vaults hiding their metadata are
+    /// left out
+    pub fn find_url(&mut self, url: &Site) -> anyhow::Result<Vec<UrlMatch>> {
+        Ok(self.info()?.find_url(url))
+    }
+
+    /// entries of the vault with a URL field for the site, the password is needed when the vault
+    /// hides its metadata
+    pub fn find_url_in(
+        &mut self,
+        vault: &str,
+        url: &Site,
+        password: Option<Password>,
+    ) -> anyhow::Result<Vec<UrlMatch>> {
+        let schema = match password {
+            Some(password) => self.metadata(vault, password)?,
+            None => self.schema(vault)?,
+        };
+        let info: Info = BTreeMap::from([(self.resolve(vault), schema)]).into();
+        Ok(info.find_url(url))
+    }
+
+    /// check the vaults, given with their passwords, see [ManagerMessage::Audit]
+    pub fn audit(
+        &mut self,
+        vaults: Vec<(String, Password)>,
+        settings: AuditSettings,
+    ) -> anyhow::Result<Audit> {
+        expect!(
+            self.send(ManagerMessage::Audit(vaults, settings))?,
+            Output::Audit
+        )
+    }
+
+    /// problems with the registered vaults and their files
+    pub fn doctor(&mut self) -> anyhow::Result<Vec<Problem>> {
+        expect!(self.send(ManagerMessage::Doctor)?, Output::Problems)
+    }
+
+    /// apply the fix for the problem, see [ManagerMessage::Repair]
+    pub fn repair(&mut self, problem: Problem, password: Option<Password>) -> anyhow::Result<()> {
+        self.send(ManagerMessage::Repair(problem, password))?;
+        Ok(())
+    }
+
+    /// unlock the vault for a session of operations, see [VaultManager::unlock]
+    pub fn unlock(
+        &self,
+        vault: &str,
+        password: Password,
+        timeout: Duration,
+    ) -> anyhow::Result<VaultSession> {
+        self.manager.unlock(vault, password, timeout)
+    }
+}
@@ -1,5 +1,5 @@
 use core::panic;
-use std::{fs, path::PathBuf};
+use std::{io::Write, path::PathBuf};
 
 use figment::{providers::Format, Error, Figment, Provider};
 use serde::{Deserialize, Serialize};
@@ -16,8 +16,8 @@ where
         base_dir.push(Self::name());
         base_dir
     }
-    fn from<T: Provider>(provider: T) -> Result<Self, Error> {
-        Figment::from(provider).extract()
+    fn from<T: Provider>(provider: T) -> Result<Self, Box<Error>> {
+        Ok(Figment::from(provider).extract()?)
     }
 
     // NOTE: not sure if this is the best place to be specifying the config file since it is possible
@@ -41,10 +41,10 @@ where
     fn save(&self) -> anyhow::Result<()> {
         let path = self.path();
         if let Some(dir) = path.parent() {
-            fs::create_dir_all(dir)?;
+            utils::create_dir_all(dir)?;
         }
 
-        fs::write(path, toml::to_string(&self)?)?;
+        utils::create_file(&path)?.write_all(toml::to_string(&self)?.as_bytes())?;
         Ok(())
     }
 
@@ -92,19 +92,18 @@ where
 }
 
 pub trait BaseConfig<'de>: InternalConfig<'de> {
+    /// the config from its file, which is created with the defaults when missing, a file that
+    /// doesn't parse is an error rather than being replaced
     fn load() -> anyhow::Result<Self> {
         let mut path = utils::base_path();
         path.push(Self::name());
+        if !path.exists() {
+            let config = Self::default();
+            config.save()?;
+            return Ok(config);
+        }
         let figment = Self::figment().merge(figment::providers::Toml::file_exact(path));
-        let config = match figment.extract() {
-            Ok(config) => config,
-            Err(_e) => {
-                let config = Self::default();
-                config.save()?;
-                config
-            }
-        };
-        Ok(config)
+        Ok(figment.extract()?)
     }
 
     fn load_err() -> Self {
@@ -1,4 +1,7 @@
-use std::{collections::BTreeMap, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
 
 use figment::{
     value::{Dict, Map},
@@ -6,13 +9,78 @@ use figment::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::config::internal_config::InternalConfig;
+use crate::{
+    attachment::AttachmentLimits, config::internal_config::InternalConfig, file::PermissionCheck,
+    policy::AgePolicy, strength::StrengthPolicy,
+};
 
 use super::internal_config::BaseConfig;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ManagerConfig {
     pub map: BTreeMap<String, PathBuf>,
+    /// vault used when a client doesn't name one
+    pub default_vault: String,
+    /// alternative names for vaults, alias -> vault
+    pub aliases: BTreeMap<String, String>,
+    /// what to do when a vault file can be modified by other users
+    pub permissions: PermissionCheck,
+    /// overwrite vault, record and backup files before deleting them
+    pub secure_delete: bool,
+    /// how large attachments can be, in bytes
+    pub attachment_limits: AttachmentLimits,
+    /// vaults whose tags, folders and usernames, URLs and emails are only readable with the
+    /// password
+    pub hidden_metadata: BTreeSet<String>,
+    /// vaults whose usernames, URLs and emails are only readable with the password, other vaults
+    /// show them without it
+    pub private_fields: BTreeSet<String>,
+    /// how many days the entries of each vault may go unchanged, by vault name
+    pub age_policies: BTreeMap<String, AgePolicy>,
+    /// how strong manually entered passwords have to be, by vault name
+    pub strength_policies: BTreeMap<String, StrengthPolicy>,
+}
+
+impl Default for ManagerConfig {
+    fn default() -> Self {
+        Self {
+            map: BTreeMap::new(),
+            default_vault: "default".into(),
+            aliases: BTreeMap::new(),
+            permissions: PermissionCheck::default(),
+            secure_delete: false,
+            attachment_limits: AttachmentLimits::default(),
+            hidden_metadata: BTreeSet::new(),
+            private_fields: BTreeSet::new(),
+            age_policies: BTreeMap::new(),
+            strength_policies: BTreeMap::new(),
+        }
+    }
+}
+
+impl ManagerConfig {
+    /// the vault name an alias refers to, or the name itself when it isn't an alias
+    pub fn resolve(&self, name: &str) -> String {
+        self.aliases
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// whether the name is already taken by a vault or an alias
+    pub fn is_taken(&self, name: &str) -> bool {
+        self.map.contains_key(name) || self.aliases.contains_key(name)
+    }
+
+    /// drop the vault along with its aliases and every setting kept for it
+    pub fn forget_vault(&mut self, name: &str) {
+        self.map.remove(name);
+        self.aliases.retain(|_, vault| vault != name);
+        self.hidden_metadata.remove(name);
+        self.private_fields.remove(name);
+        self.age_policies.remove(name);
+        self.strength_policies.remove(name);
+    }
 }
 
 impl<'de> InternalConfig<'de> for ManagerConfig {
@@ -32,3 +100,31 @@ impl Provider for ManagerConfig {
         figment::providers::Serialized::defaults(Self::default()).data()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ManagerConfig;
+
+    #[test]
+    fn forget_vault_drops_its_settings() {
+        let mut config = ManagerConfig::default();
+        for name in ["gone", "kept"] {
+            config.map.insert(name.into(), name.into());
+            config.aliases.insert(format!("{name}-alias"), name.into());
+            config.hidden_metadata.insert(name.into());
+            config.private_fields.insert(name.into());
+            config.age_policies.insert(name.into(), Default::default());
+            config
+                .strength_policies
+                .insert(name.into(), Default::default());
+        }
+        config.forget_vault("gone");
+        assert_eq!(config.map.keys().collect::<Vec<_>>(), ["kept"]);
+        assert_eq!(config.aliases.keys().collect::<Vec<_>>(), ["kept-alias"]);
+        assert!(!config.hidden_metadata.contains("gone"));
+        assert!(!config.private_fields.contains("gone"));
+        assert!(!config.age_policies.contains_key("gone"));
+        assert!(!config.strength_policies.contains_key("gone"));
+        assert!(config.strength_policies.contains_key("kept"));
+    }
+}
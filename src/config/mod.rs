@@ -1,3 +1,4 @@
 pub mod internal_config;
 pub mod manager_config;
+pub mod template_config;
 pub mod vault_config;
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+
+use figment::{
+    providers::Env,
+    value::{Dict, Map},
+    Error, Figment, Metadata, Profile, Provider,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::internal_config::InternalConfig, template::Template};
+
+use super::internal_config::BaseConfig;
+
+/// entry templates shared by the clients, the built-in ones are only used when the file leaves out
+/// `templates`, so templates removed from the file stay removed
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemplateConfig {
+    pub templates: BTreeMap<String, Template>,
+}
+
+impl Default for TemplateConfig {
+    fn default() -> Self {
+        Self {
+            templates: Template::defaults().into_iter().collect(),
+        }
+    }
+}
+
+impl TemplateConfig {
+    pub fn get(&self, name: &str) -> Option<&Template> {
+        self.templates.get(name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.templates.keys().cloned().collect()
+    }
+}
+
+impl<'de> InternalConfig<'de> for TemplateConfig {
+    fn name() -> String {
+        "templates.toml".into()
+    }
+
+    /// without the defaults underneath, they would be merged into the file's templates
+    fn figment() -> Figment {
+        Figment::new().merge(Env::prefixed("PANTS_"))
+    }
+}
+
+impl<'de> BaseConfig<'de> for TemplateConfig {}
+
+impl Provider for TemplateConfig {
+    fn metadata(&self) -> Metadata {
+        Metadata::named("Template config")
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, Error> {
+        figment::providers::Serialized::defaults(Self::default()).data()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use figment::providers::{Format, Toml};
+
+    use super::TemplateConfig;
+    use crate::config::internal_config::InternalConfig;
+
+    fn parse(file: &str) -> anyhow::Result<TemplateConfig> {
+        Ok(TemplateConfig::figment()
+            .merge(Toml::string(file))
+            .extract()?)
+    }
+
+    #[test]
+    fn file_replaces_built_ins() {
+        let config = parse(
+            r#"
+            [[templates.note.fields]]
+            name = "Note"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.names(), vec!["note".to_string()]);
+    }
+
+    #[test]
+    fn built_ins_without_templates() {
+        let config = parse("").unwrap();
+        assert_eq!(config.names(), TemplateConfig::default().names());
+    }
+
+    #[test]
+    fn invalid_file_is_an_error() {
+        assert!(parse("[[templates.note.fields]]\nkind = 3").is_err());
+    }
+
+    #[test]
+    fn card_expiry_takes_month_and_year() {
+        let config = TemplateConfig::default();
+        let template = config.get("credit-card").unwrap();
+        let expiry = template
+            .fields
+            .iter()
+            .find(|field| field.name == "Expiry")
+            .unwrap();
+        for value in ["12/27", "12/2027", "2027-12-31"] {
+            assert!(expiry.kind.is_valid(value), "{value} was rejected");
+        }
+        for value in ["13/27", "12/", "27"] {
+            assert!(!expiry.kind.is_valid(value), "{value} was accepted");
+        }
+    }
+}
@@ -6,28 +6,100 @@ use figment::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{file::SaveDir, utils};
+use crate::{
+    attachment::AttachmentLimits,
+    file::{PermissionCheck, SaveDir},
+    policy::AgePolicy,
+    strength::StrengthPolicy,
+    utils,
+};
 
 use super::internal_config::InternalConfig;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VaultConfig {
     save_dir: PathBuf,
+    permissions: PermissionCheck,
+    secure_delete: bool,
+    attachment_limits: AttachmentLimits,
+    hide_metadata: bool,
+    public_fields: bool,
+    age_policy: AgePolicy,
+    strength_policy: StrengthPolicy,
 }
 
 impl Default for VaultConfig {
     fn default() -> Self {
         let save_dir = utils::base_path();
-        Self { save_dir }
+        Self {
+            save_dir,
+            permissions: PermissionCheck::default(),
+            secure_delete: false,
+            attachment_limits: AttachmentLimits::default(),
+            hide_metadata: false,
+            public_fields: true,
+            age_policy: AgePolicy::default(),
+            strength_policy: StrengthPolicy::default(),
+        }
     }
 }
 
 impl VaultConfig {
     pub fn new(save_dir: PathBuf) -> Self {
-        Self { save_dir }
+        Self {
+            save_dir,
+            permissions: PermissionCheck::default(),
+            secure_delete: false,
+            attachment_limits: AttachmentLimits::default(),
+            hide_metadata: false,
+            public_fields: true,
+            age_policy: AgePolicy::default(),
+            strength_policy: StrengthPolicy::default(),
+        }
+    }
+    pub fn permissions(mut self, permissions: PermissionCheck) -> Self {
+        self.permissions = permissions;
+        self
+    }
+    pub fn secure_delete(mut self, secure_delete: bool) -> Self {
+        self.secure_delete = secure_delete;
+        self
+    }
+    pub fn attachment_limits(mut self, attachment_limits: AttachmentLimits) -> Self {
+        self.attachment_limits = attachment_limits;
+        self
+    }
+    pub fn hide_metadata(mut self, hide_metadata: bool) -> Self {
+        self.hide_metadata = hide_metadata;
+        self
+    }
+    pub fn public_fields(mut self, public_fields: bool) -> Self {
+        self.public_fields = public_fields;
+        self
+    }
+    pub fn age_policy(mut self, age_policy: AgePolicy) -> Self {
+        self.age_policy = age_policy;
+        self
+    }
+    pub fn strength_policy(mut self, strength_policy: StrengthPolicy) -> Self {
+        self.strength_policy = strength_policy;
+        self
+    }
+    pub fn policy(&self) -> &AgePolicy {
+        &self.age_policy
+    }
+    pub fn strength(&self) -> StrengthPolicy {
+        self.strength_policy
+    }
+    pub fn limits(&self) -> AttachmentLimits {
+        self.attachment_limits
     }
     pub fn save_dir(&self) -> SaveDir {
         SaveDir::new(self.save_dir.to_path_buf())
+            .with_permissions(self.permissions)
+            .with_secure_delete(self.secure_delete)
+            .with_hidden_metadata(self.hide_metadata)
+            .with_public_fields(self.public_fields)
     }
 }
 
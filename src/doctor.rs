@@ -0,0 +1,164 @@
+use std::{
+    collections::BTreeSet,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::manager_config::ManagerConfig,
+    file::{ProjectFile, SaveDir},
+    utils,
+};
+
+/// a problem found while checking the state of the vaults on disk
+#[derive(Debug, Clone)]
+pub enum Problem {
+    /// a registered vault points to a directory that doesn't exist
+    MissingDirectory { vault: String, path: PathBuf },
+    /// a vault directory exists on disk without being registered
+    UnregisteredVault { name: String, path: PathBuf },
+    /// a record file left behind by an interrupted transaction
+    InterruptedTransaction { vault: String, path: PathBuf },
+    /// a backup or record file whose name doesn't contain a valid timestamp
+    MalformedTimestamp { vault: String, path: PathBuf },
+    /// a file or directory that other users can access
+    Permissions { path: PathBuf, mode: u32 },
+}
+
+impl Problem {
+    /// description of the fix that [crate::vault::manager::VaultManager::repair] applies
+    pub fn fix(&self) -> String {
+        match self {
+            Self::MissingDirectory { .. } => "unregister the vault".into(),
+            Self::UnregisteredVault { name, .. } => format!("register the vault as `{name}`"),
+            Self::InterruptedTransaction { .. } => "replay the unfinished transaction".into(),
+            Self::MalformedTimestamp { .. } => "move the file to the quarantine directory".into(),
+            Self::Permissions { .. } => "restrict access to the owner".into(),
+        }
+    }
+
+    pub fn needs_password(&self) -> bool {
+        matches!(self, Self::InterruptedTransaction { .. })
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingDirectory { vault, path } => {
+                write!(f, "{vault}: directory {path:?} does not exist")
+            }
+            Self::UnregisteredVault { path, .. } => {
+                write!(f, "{path:?}: vault directory is not registered")
+            }
+            Self::InterruptedTransaction { vault, path } => {
+                write!(f, "{vault}: unfinished transaction in {path:?}")
+            }
+            Self::MalformedTimestamp { vault, path } => {
+                write!(f, "{vault}: malformed timestamp in {path:?}")
+            }
+            Self::Permissions { path, mode } => {
+                write!(f, "{path:?}: accessible by other users (mode {mode:o})")
+            }
+        }
+    }
+}
+
+/// look for problems with the vaults known to the config and the files in the base directory
+pub fn check(config: &ManagerConfig) -> Vec<Problem> {
+    let mut problems = vec![];
+    for (vault, path) in &config.map {
+        if !path.exists() {
+            problems.push(Problem::MissingDirectory {
+                vault: vault.to_string(),
+                path: path.to_path_buf(),
+            });
+            continue;
+        }
+        let save_dir = SaveDir::new(path.to_path_buf());
+        for record in save_dir.record_file_all() {
+            problems.push(Problem::InterruptedTransaction {
+                vault: vault.to_string(),
+                path: record.path(),
+            });
+        }
+        for malformed in save_dir.malformed_files() {
+            problems.push(Problem::MalformedTimestamp {
+                vault: vault.to_string(),
+                path: malformed,
+            });
+        }
+    }
+
+    let base_path = utils::base_path();
+    let registered: BTreeSet<&PathBuf> = config.map.values().collect();
+    if let Ok(entries) = fs::read_dir(&base_path) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() && is_vault_dir(&path) && !registered.contains(&path) {
+                problems.push(Problem::UnregisteredVault {
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    path,
+                });
+            }
+        }
+    }
+
+    permission_problems(&base_path, &mut problems);
+    for path in config.map.values() {
+        if !path.starts_with(&base_path) {
+            permission_problems(path, &mut problems);
+        }
+    }
+
+    problems
+}
+
+fn is_vault_dir(path: &Path) -> bool {
+    let save_dir = SaveDir::new(path.to_path_buf());
+    save_dir.vault_file().exists() || save_dir.schema_file().exists()
+}
+
+#[cfg(unix)]
+fn permission_problems(path: &Path, problems: &mut Vec<Problem>) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return;
+    };
+    if metadata.file_type().is_symlink() {
+        return;
+    }
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        problems.push(Problem::Permissions {
+            path: path.to_path_buf(),
+            mode,
+        });
+    }
+    if metadata.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                permission_problems(&entry.path(), problems);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn permission_problems(_path: &Path, _problems: &mut Vec<Problem>) {}
+
+/// restrict a file or directory to its owner
+#[cfg(unix)]
+pub fn restrict(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = if path.is_dir() { 0o700 } else { 0o600 };
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+pub fn restrict(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
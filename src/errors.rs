@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -52,6 +54,22 @@ pub enum ClientError {
     NoChanges,
     #[error("Couldn't rename the entry")]
     CantRename,
+    #[error("No template named `{0}`")]
+    NoTemplate(String),
+    #[error("Couldn't get a name from the file path")]
+    NoFileName,
+    #[error("The entry has no password fields, name the field to regenerate")]
+    NoPasswordField,
+    #[error("The entry has no field `{0}`")]
+    NoField(String),
+    #[error("No entries match the filter")]
+    NoMatches,
+    #[error("`{0}` is not a valid URL")]
+    InvalidUrl(String),
+    #[error("The vault session is locked, unlock the vault again")]
+    SessionLocked,
+    #[error("The vault password is needed for this")]
+    PasswordRequired,
 }
 
 #[derive(Debug, Error)]
@@ -62,4 +80,52 @@ pub enum ManagerError {
     VaultDoesNotExist,
     #[error("Tried to delete a non-empty vault")]
     NonEmptyVault,
+    #[error("Source and destination vault are the same")]
+    SameVault,
+    #[error("Alias does not exist")]
+    AliasDoesNotExist,
+    #[error("Vault names can't be paths")]
+    InvalidName,
+}
+
+#[derive(Debug, Error)]
+pub enum PermissionError {
+    #[error("{0:?} is writable by other users")]
+    SharedWritable(PathBuf),
+    #[error("{0:?} is owned by another user")]
+    ForeignOwner(PathBuf),
+}
+
+#[derive(Debug, Error)]
+pub enum FieldError {
+    #[error("Field `{0}` is not a valid {1}")]
+    Invalid(String, crate::store::FieldKind),
+}
+
+#[derive(Debug, Error)]
+pub enum TotpError {
+    #[error("Not a valid base32 secret")]
+    Secret,
+    #[error("Not a valid otpauth URI: {0}")]
+    Uri(&'static str),
+    #[error("Unsupported algorithm `{0}`")]
+    Algorithm(String),
+}
+
+#[derive(Debug, Error)]
+pub enum AttachmentError {
+    #[error("No attachment named `{0}`")]
+    NoAttachment(String),
+    #[error("Attachments can be at most {0} bytes")]
+    TooLarge(u64),
+    #[error("Attachments in a vault can take at most {0} bytes")]
+    VaultFull(u64),
+}
+
+#[derive(Debug, Error)]
+pub enum PassphraseError {
+    #[error("Could not read the wordlist: {0}")]
+    Read(#[from] std::io::Error),
+    #[error("The wordlist needs at least two different words")]
+    TooShort,
 }
@@ -3,7 +3,7 @@ use std::{
     fs::{self, File},
     io::{Read, Write},
     marker::PhantomData,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use chrono::{DateTime, Local};
@@ -11,9 +11,11 @@ use glob::glob;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    errors::SaveError,
+    attachment::{Attachment, AttachmentIndex},
+    errors::{PermissionError, SaveError},
     schema::Schema,
-    utils::{format_date, now, read_date},
+    secure::Encrypted,
+    utils::{self, format_date, now, read_date},
     vault::encrypted::{RecordEncrypted, VaultEncrypted},
 };
 
@@ -27,10 +29,10 @@ where
     fn create(&self) -> anyhow::Result<File> {
         let path = self.path();
         if let Some(dir) = path.parent() {
-            fs::create_dir_all(dir)?;
+            utils::create_dir_all(dir)?;
         }
 
-        let file = File::create(path)?;
+        let file = utils::create_file(&path)?;
         Ok(file)
     }
 
@@ -165,6 +167,12 @@ where
     }
 }
 
+impl<Data> TimestampedFile<Data> {
+    pub fn timestamp(&self) -> DateTime<Local> {
+        self.timestamp
+    }
+}
+
 impl<'a, Data> NonTimestampedFile<Data>
 where
     Self: Name,
@@ -187,6 +195,49 @@ pub type VaultFile = NonTimestampedFile<VaultEncrypted>;
 pub type RecordFile = TimestampedFile<RecordEncrypted>;
 pub type BackupFile = TimestampedFile<VaultEncrypted>;
 pub type SchemaFile = NonTimestampedFile<Schema>;
+pub type AttachmentIndexFile = NonTimestampedFile<Encrypted<AttachmentIndex>>;
+
+/// a single attachment, stored next to the [AttachmentIndexFile] under its id
+#[derive(Debug, Clone)]
+pub struct AttachmentFile {
+    id: String,
+    base_path: PathBuf,
+}
+
+impl<'de> ProjectFile<'de, Encrypted<Attachment>> for AttachmentFile {
+    fn path(&self) -> PathBuf {
+        let mut path = self.base_path();
+        path.push(AttachmentIndexFile::name());
+        path.push(&self.id);
+        path.set_extension("json");
+        path
+    }
+
+    fn base_path(&self) -> PathBuf {
+        self.base_path.to_path_buf()
+    }
+}
+
+/// the attachment index encrypted under a new key, kept next to the current index until the
+/// vault is saved under that key too
+#[derive(Debug, Clone)]
+pub struct RekeyedIndexFile {
+    base_path: PathBuf,
+}
+
+impl<'de> ProjectFile<'de, Encrypted<AttachmentIndex>> for RekeyedIndexFile {
+    fn path(&self) -> PathBuf {
+        let mut path = self.base_path();
+        path.push(AttachmentIndexFile::name());
+        path.push("rekeyed");
+        path.set_extension("json");
+        path
+    }
+
+    fn base_path(&self) -> PathBuf {
+        self.base_path.to_path_buf()
+    }
+}
 
 pub trait Name {
     fn name() -> String;
@@ -216,18 +267,137 @@ impl Name for SchemaFile {
     }
 }
 
+impl Name for AttachmentIndexFile {
+    fn name() -> String {
+        "attachment".to_string()
+    }
+}
+
+/// how strictly to treat vault files that other users can modify
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionCheck {
+    /// don't check the file
+    Ignore,
+    /// print a warning and continue
+    #[default]
+    Warn,
+    /// refuse to load the file
+    Refuse,
+}
+
 #[derive(Debug, Clone)]
 pub struct SaveDir {
     base_path: PathBuf,
+    permissions: PermissionCheck,
+    secure_delete: bool,
+    hide_metadata: bool,
+    public_fields: bool,
 }
 
 impl SaveDir {
     pub fn new(base_path: PathBuf) -> Self {
-        Self { base_path }
+        Self {
+            base_path,
+            permissions: PermissionCheck::default(),
+            secure_delete: false,
+            hide_metadata: false,
+            public_fields: true,
+        }
+    }
+
+    pub fn with_permissions(mut self, permissions: PermissionCheck) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
+    /// overwrite files before removing them, see [utils::erase_file]
+    pub fn with_secure_delete(mut self, secure_delete: bool) -> Self {
+        self.secure_delete = secure_delete;
+        self
+    }
+
+    /// leave tags, folders and public values out of the schema file
+    pub fn with_hidden_metadata(mut self, hide_metadata: bool) -> Self {
+        self.hide_metadata = hide_metadata;
+        self
+    }
+
+    pub fn hides_metadata(&self) -> bool {
+        self.hide_metadata
+    }
+
+    /// write the values of the public fields to the schema file, see
+    /// [crate::store::FieldKind::is_public]
+    pub fn with_public_fields(mut self, public_fields: bool) -> Self {
+        self.public_fields = public_fields;
+        self
+    }
+
+    pub fn publishes_fields(&self) -> bool {
+        self.public_fields
+    }
+
+    /// remove a file in the directory, erasing it first when secure deletion is on
+    pub fn delete_file(&self, path: &Path) -> Result<(), std::io::Error> {
+        if self.secure_delete {
+            utils::erase_file(path)
+        } else {
+            fs::remove_file(path)
+        }
+    }
+
+    /// check that a file about to be loaded is only writable and owned by the current user
+    pub fn check_permissions(&self, path: &Path) -> anyhow::Result<()> {
+        if self.permissions == PermissionCheck::Ignore {
+            return Ok(());
+        }
+        let problem = if utils::foreign_owner(path)? {
+            Some(PermissionError::ForeignOwner(path.to_path_buf()))
+        } else if utils::shared_writable(path)? {
+            Some(PermissionError::SharedWritable(path.to_path_buf()))
+        } else {
+            None
+        };
+        match (problem, self.permissions) {
+            (Some(problem), PermissionCheck::Refuse) => Err(problem.into()),
+            (Some(problem), _) => {
+                eprintln!("Warning: {problem}");
+                Ok(())
+            }
+            (None, _) => Ok(()),
+        }
     }
 
     pub fn remove(&self) -> Result<(), std::io::Error> {
-        fs::remove_dir_all(&self.base_path)
+        if self.secure_delete {
+            utils::erase_dir(&self.base_path)
+        } else {
+            fs::remove_dir_all(&self.base_path)
+        }
+    }
+
+    pub fn base_path(&self) -> PathBuf {
+        self.base_path.to_path_buf()
+    }
+
+    /// backup and record files whose names don't contain a readable timestamp
+    pub fn malformed_files(&self) -> Vec<PathBuf> {
+        let mut malformed = self.timestamped_file_malformed::<RecordEncrypted>();
+        malformed.extend(self.timestamped_file_malformed::<VaultEncrypted>());
+        malformed
+    }
+
+    /// move a file into the vault's quarantine directory so it is no longer picked up
+    pub fn quarantine(&self, path: &Path) -> Result<PathBuf, std::io::Error> {
+        let mut target = self.base_path.clone();
+        target.push("quarantine");
+        utils::create_dir_all(&target)?;
+        if let Some(name) = path.file_name() {
+            target.push(name);
+        }
+        fs::rename(path, &target)?;
+        Ok(target)
     }
 
     pub fn vault_file(&self) -> VaultFile {
@@ -262,6 +432,69 @@ impl SaveDir {
         self.timestamped_file_all()
     }
 
+    pub fn attachment_index_file(&self) -> AttachmentIndexFile {
+        self.nontimestamped_file()
+    }
+
+    pub fn attachment_file(&self, id: &str) -> AttachmentFile {
+        AttachmentFile {
+            id: id.into(),
+            base_path: self.base_path.to_path_buf(),
+        }
+    }
+
+    pub fn rekeyed_index_file(&self) -> RekeyedIndexFile {
+        RekeyedIndexFile {
+            base_path: self.base_path.to_path_buf(),
+        }
+    }
+
+    /// ids of the attachment files in the directory, whether an index refers to them or not
+    pub fn attachment_ids(&self) -> Vec<String> {
+        let indexes = [
+            self.attachment_index_file().path(),
+            self.rekeyed_index_file().path(),
+        ];
+        let Ok(entries) = fs::read_dir(self.attachment_dir()) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| !indexes.contains(path))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .collect()
+    }
+
+    /// directory holding the attachment index and the attachments
+    pub fn attachment_dir(&self) -> PathBuf {
+        let mut path = self.base_path.clone();
+        path.push(AttachmentIndexFile::name());
+        path
+    }
+
+    /// copy of the attachment directory taken along with a backup
+    pub fn attachment_backup_dir(&self, backup: &BackupFile) -> PathBuf {
+        let mut path = self.base_path.clone();
+        path.push(BackupFile::name());
+        path.push(format!(
+            "{}-{}",
+            AttachmentIndexFile::name(),
+            format_date(backup.timestamp())
+        ));
+        path
+    }
+
+    /// remove a directory in the save directory, erasing its files when secure deletion is on
+    pub fn delete_dir(&self, path: &Path) -> Result<(), std::io::Error> {
+        if self.secure_delete {
+            utils::erase_dir(path)
+        } else {
+            fs::remove_dir_all(path)
+        }
+    }
+
     fn nontimestamped_file<'de, Data>(&self) -> NonTimestampedFile<Data>
     where
         NonTimestampedFile<Data>: Name,
@@ -303,6 +536,25 @@ impl SaveDir {
         paths
     }
 
+    fn timestamped_file_malformed<'de, Data>(&self) -> Vec<PathBuf>
+    where
+        TimestampedFile<Data>: Name,
+        Data: Serialize + Deserialize<'de>,
+    {
+        let mut path = self.base_path.clone();
+        path.push(TimestampedFile::name());
+        path.push(format!("{}-*.json", TimestampedFile::name()));
+        glob(path.to_str().unwrap())
+            .expect("Failed to read glob pattern")
+            .flatten()
+            .filter(|entry| {
+                let file_name = entry.file_stem().unwrap().to_str().unwrap();
+                let split = file_name.split_once('-').unwrap();
+                read_date(split.1).is_err()
+            })
+            .collect()
+    }
+
     fn timestamped_file_recent<'de, Data>(&self) -> Option<TimestampedFile<Data>>
     where
         TimestampedFile<Data>: Name,
@@ -317,7 +569,9 @@ impl SaveDir {
                 Ok(p) => {
                     let file_name = p.file_stem().unwrap().to_str().unwrap();
                     let split = file_name.split_once('-').unwrap();
-                    let time = read_date(split.1).unwrap();
+                    let Ok(time) = read_date(split.1) else {
+                        return acc;
+                    };
                     match acc {
                         None => Some(TimestampedFile::new(self.base_path.to_path_buf(), time)),
                         Some(ref f) => {
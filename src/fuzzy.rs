@@ -0,0 +1,149 @@
+//! approximate matching of entry names so lookups survive partial names and typos
+
+use std::fmt::Display;
+
+/// score of a candidate that is exactly the query
+pub const EXACT: u32 = 1000;
+
+/// an entry matching a query, higher scores are better matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub vault: String,
+    pub key: String,
+    pub score: u32,
+}
+
+impl Match {
+    pub fn is_exact(&self) -> bool {
+        self.score == EXACT
+    }
+}
+
+impl Display for Match {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.key, self.vault)
+    }
+}
+
+/// how well `candidate` matches `query`, from exact, prefix and substring matches down to the
+/// query's characters appearing in order and finally a couple of typos, [None] when it doesn't
+pub fn score(query: &str, candidate: &str) -> Option<u32> {
+    if query == candidate {
+        return Some(EXACT);
+    }
+    let query = query.to_lowercase();
+    let candidate = candidate.to_lowercase();
+    let extra = (candidate
+        .chars()
+        .count()
+        .saturating_sub(query.chars().count())) as u32;
+    if query.is_empty() {
+        None
+    } else if query == candidate {
+        Some(900)
+    } else if candidate.starts_with(&query) {
+        Some(800u32.saturating_sub(extra).max(700))
+    } else if let Some(position) = candidate.find(&query) {
+        Some(600u32.saturating_sub(position as u32 + extra).max(500))
+    } else if let Some(gaps) = subsequence_gaps(&query, &candidate) {
+        Some(400u32.saturating_sub(gaps).max(300))
+    } else {
+        let allowed = if query.chars().count() <= 4 { 1 } else { 2 };
+        let distance = edit_distance(&query, &candidate);
+        (distance <= allowed).then(|| 200 - 50 * distance as u32)
+    }
+}
+
+/// characters skipped in `candidate` to find the characters of `query` in order
+fn subsequence_gaps(query: &str, candidate: &str) -> Option<u32> {
+    let mut candidate = candidate.chars();
+    let mut gaps = 0;
+    let mut started = false;
+    for c in query.chars() {
+        loop {
+            let next = candidate.next()?;
+            if next == c {
+                started = true;
+                break;
+            }
+            if started {
+                gaps += 1;
+            }
+        }
+    }
+    Some(gaps)
+}
+
+/// edits (insertions, deletions, substitutions and swapping neighbours) turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{score, EXACT};
+
+    #[test]
+    fn exact() {
+        assert_eq!(score("github", "github"), Some(EXACT));
+        assert_eq!(score("GitHub", "github"), Some(900));
+    }
+
+    #[test]
+    fn prefix_and_substring() {
+        assert_eq!(score("git", "github"), Some(797));
+        assert_eq!(score("hub", "github"), Some(594));
+    }
+
+    #[test]
+    fn characters_in_order() {
+        assert_eq!(score("gthb", "github"), Some(398));
+    }
+
+    #[test]
+    fn typos() {
+        assert_eq!(score("gihtub", "github"), Some(150));
+        assert_eq!(score("gitlub", "github"), Some(150));
+        assert_eq!(score("gxtlxb", "github"), None);
+        assert_eq!(score("bnak", "bank"), Some(150));
+    }
+
+    #[test]
+    fn no_match() {
+        assert_eq!(score("bank", "github"), None);
+        assert_eq!(score("", "github"), None);
+    }
+
+    #[test]
+    fn closer_matches_score_higher() {
+        let scores: Vec<u32> = ["github", "git", "hub", "gthb", "gihtub"]
+            .iter()
+            .map(|query| score(query, "github").unwrap())
+            .collect();
+        assert!(
+            scores.windows(2).all(|pair| pair[0] > pair[1]),
+            "{scores:?}"
+        );
+    }
+}
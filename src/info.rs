@@ -2,7 +2,12 @@ use std::{collections::BTreeMap, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
-use crate::schema::Schema;
+use crate::{
+    fuzzy::{score, Match},
+    schema::Schema,
+    store::FieldKind,
+    url::{Site, UrlMatch},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Info {
@@ -16,6 +21,93 @@ impl Info {
     pub fn insert(&mut self, key: String, value: Schema) {
         self.data.insert(key, value);
     }
+
+    /// the matching entries of every vault, see [Schema::filter], vaults without any are dropped
+    /// along with the ones that hide their metadata
+    pub fn filter(&self, tags: &[String], folder: Option<&str>) -> Info {
+        self.data
+            .iter()
+            .filter(|(_, schema)| !schema.hidden)
+            .map(|(vault, schema)| (vault.clone(), schema.filter(tags, folder)))
+            .filter(|(_, schema)| !schema.is_empty())
+            .collect::<BTreeMap<_, _>>()
+            .into()
+    }
+
+    /// entries of every vault matching `query`, best matches first
+    pub fn find(&self, query: &str) -> Vec<Match> {
+        let mut matches: Vec<Match> = self
+            .data
+            .iter()
+            .flat_map(|(vault, schema)| {
+                schema.data.keys().filter_map(move |key| {
+                    score(query, key).map(|score| Match {
+                        vault: vault.clone(),
+                        key: key.clone(),
+                        score,
+                    })
+                })
+            })
+            .collect();
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.vault.cmp(&b.vault))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        matches
+    }
+
+    /// URL fields of every vault matching the site, best matches first, only vaults publishing
+    /// their public fields have URLs to match
+    pub fn find_url(&self, site: &Site) -> Vec<UrlMatch> {
+        let mut matches: Vec<UrlMatch> = self
+            .data
+            .iter()
+            .flat_map(|(vault, schema)| {
+                schema.public.iter().flat_map(move |(key, values)| {
+                    values.iter().filter_map(move |(field, url)| {
+                        if schema.kind(key, field) != FieldKind::Url {
+                            return None;
+                        }
+                        let kind = site.matched_by(&url.parse().ok()?)?;
+                        Some(UrlMatch {
+                            vault: vault.clone(),
+                            key: key.clone(),
+                            field: field.clone(),
+                            url: url.clone(),
+                            kind,
+                        })
+                    })
+                })
+            })
+            .collect();
+        matches.sort_by(|a, b| {
+            a.kind
+                .cmp(&b.kind)
+                .then_with(|| a.vault.cmp(&b.vault))
+                .then_with(|| a.key.cmp(&b.key))
+        });
+        matches
+    }
+
+    /// vaults whose tags and folders can't be read without the password
+    pub fn hidden(&self) -> Vec<&String> {
+        self.data
+            .iter()
+            .filter(|(_, schema)| schema.hidden)
+            .map(|(vault, _)| vault)
+            .collect()
+    }
+
+    /// vaults whose public fields can't be read without the password
+    pub fn unpublished(&self) -> Vec<&String> {
+        self.data
+            .iter()
+            .filter(|(_, schema)| schema.unpublished)
+            .map(|(vault, _)| vault)
+            .collect()
+    }
 }
 
 impl From<BTreeMap<String, Schema>> for Info {
@@ -40,12 +132,7 @@ impl Display for Info {
             if schema.is_empty() {
                 writeln!(f, "  is empty")?;
             } else {
-                for (entry, fields) in &schema.data {
-                    writeln!(f, " {entry}:")?;
-                    for field in fields {
-                        writeln!(f, "  - {field}")?;
-                    }
-                }
+                schema.write_entries(f, 1)?;
             }
         }
         Ok(())
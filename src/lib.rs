@@ -12,14 +12,37 @@
 //! password to the new master password and creating a backup of the old vault if you need to
 //! restore the previous password.
 //!
+//! Programs using pants as a library go through [client::PantsClient], which has a method for
+//! each operation returning its result, like `schema(vault)` or `get(vault, key, password)`. A
+//! vault can also be unlocked once with [client::PantsClient::unlock] to run several operations
+//! on the returned [vault::interface::VaultSession] without deriving the key again. The session locks itself
+//! after going unused for the given timeout and zeroes the key when it does. Gets, updates,
+//! changes, renames and deletes sent together as a [message::Message::Batch] are saved as one
+//! transaction, a step that fails leaves the vault as it was.
+//!
 //! Whenever pulling a password out of the vault it will copy it to your clipboard for a few
 //! seconds and then attempt to restore the previous contents of your clipboard to prevent
 //! unintentional pastes of the password.
 //!
+//! Every field of an entry has a kind: password, username, URL, email, note, TOTP seed, date,
+//! number or generic. Values are checked against their kind when they're stored, and only
+//! password, TOTP seed and generic fields are treated as secret, the others are printed directly.
+//! Fields stored by older versions get their kind guessed from the field name. Usernames, URLs
+//! and emails are public: they're kept next to the entry names in the vault's unencrypted schema
+//! so `pants list`, `pants find-url` and the GUI can show them without the vault password,
+//! everything else stays encrypted. `pants vault unpublish <vault>` keeps them encrypted as well
+//! and removes them from the schema, `pants vault publish <vault>` shows them again.
+//!
 //! If you need to change the default behavior like the clipboard time or the default password
 //! specification, check the `pants/*_client.toml` located in the standard config directory for
 //! your OS (e.g. ~/.local/share/ on linux)
 //!
+//! Files and directories pants creates are only accessible by their owner. When loading a vault
+//! that another user owns or can write to, pants warns by default; set `permissions` in
+//! `pants/pants.toml` to `refuse` to stop instead or `ignore` to skip the check. Setting
+//! `secure_delete = true` makes pants overwrite vault, record and backup files before removing
+//! them, this is best effort since filesystems and SSDs may keep copies of the old blocks.
+//!
 //! # Examples
 //!
 //! The basic interface operates around `new`, `add`, `get`, `update`, and `delete`.
@@ -43,6 +66,14 @@
 //! Resetting clipboard
 //! ```
 //!
+//! With `--template` the entry gets the fields of a template from `pants/templates.toml`, only
+//! asking for the values that aren't generated. The file starts out with `login`, `credit-card`,
+//! `ssh-key`, `api-token`, `wifi` and `server`, templates removed from it stay removed, and the
+//! GUI offers the same templates.
+//! ```bash
+//! $ pants add --template login github
+//! ```
+//!
 //! ## Get
 //!
 //! Retrieve an existing entry
@@ -54,6 +85,14 @@
 //! Resetting clipboard
 //! ```
 //!
+//! The entry doesn't need to be spelled out, without an exact match in the given or default vault
+//! every vault is searched for close matches, offering a choice when there's more than one.
+//! ```bash
+//! $ pants get git
+//! Using github (work)
+//! > Password for work: ********
+//! ```
+//!
 //! ## Update
 //!
 //! Update an existing entry.
@@ -66,6 +105,11 @@
 //! Resetting clipboard
 //! ```
 //!
+//! Generated fields remember the spec they were made with, as unencrypted metadata next to their
+//! kind, so regenerating them with `update`, `rotate-entry` or the GUI's "Generate" button keeps
+//! satisfying sites with odd rules. `--spec` overrides it and is remembered in turn, and "Change
+//! password spec" in `update` (or the GUI's spec input) edits it.
+//!
 //! ## Delete
 //!
 //! Remove a vault
@@ -91,27 +135,117 @@
 //!   test: password
 //! ```
 //!
+//! Entries can carry tags and sit in a folder, set with `--tag`/`--folder` on `add` or with the
+//! `tag` and `folder` commands. `list --tag work --folder infra` only lists the entries with every
+//! given tag in `infra` or one of its subfolders, and the GUI groups entries by folder with a
+//! filter box on top. Tags and folders are readable without the vault password unless the vault
+//! is hidden with `pants vault hide <vault>`, then filtering asks for the password.
+//! ```bash
+//! $ pants add --tag work --folder infra/aws aws-root
+//! $ pants list --tag work
+//! ```
+//!
 //! # Other commands
 //!
 //! Other commands include:
 //!  - backup: creates a backup of the current vault
-//!  - gen: exposes the password generator in [pants-gen](https://docs.rs/pants-gen/)
+//!  - gen: exposes the password generator in [pants-gen](https://docs.rs/pants-gen/), printing
+//!    the estimated strength of the password to stderr. The estimate also catches common
+//!    passwords, repeats, sequences, keyboard runs and years, and manually entered passwords
+//!    below a vault's minimum ask before being kept, or are refused when the minimum is enforced.
+//!    The GUI shows it as a meter under password inputs. Minimums are set per vault in
+//!    `pants/pants.toml`:
+//!    ```toml
+//!    [strength_policies.work]
+//!    min_bits = 60 # defaults to 50
+//!    enforce = true # refuse weaker passwords instead of asking
+//!    ```
+//!
+//!    `gen --passphrase` makes a passphrase of random words instead (`--words`, `--separator`,
+//!    `--capitalize`, `--digits`, `--wordlist`), also offered when adding or updating fields and
+//!    by the GUI's "Passphrase" button. The words come from a bundled list of about 1500 short
+//!    common words, around 10.6 bits a word, or a wordlist file like the
+//!    [EFF large list](https://www.eff.org/dice) at 12.9 bits a word. The default of 8 bundled
+//!    words gives about 85 bits, 6 words are enough with the EFF list. The defaults are set in
+//!    the client configs:
+//!    ```toml
+//!    [passphrase]
+//!    words = 8
+//!    separator = "-"
+//!    capitalize = false
+//!    digits = 0
+//!    wordlist = "/path/to/eff_large_wordlist.txt"
+//!    ```
+//!  - move/copy: moves or copies entries from one vault to another
+//!  - vault: renames vaults and manages vault aliases and the default vault (also settable with
+//!    `--vault` or the `PANTS_VAULT` environment variable)
+//!  - doctor: checks for unregistered or missing vaults, interrupted transactions, malformed
+//!    backups and loose file permissions, `--fix` offers to repair them
+//!  - due: lists the entries past or near their maximum age. Ages are counted from the last
+//!    time an entry's values changed, entries stored before that was recorded show up with an
+//!    unknown age. `rotate-entry` generates new values for an entry's password fields and saves
+//!    them in one step, and the GUI marks due entries. Maximum ages are set per vault in
+//!    `pants/pants.toml`:
+//!    ```toml
+//!    [age_policies.work]
+//!    max_age = 90 # days, for every entry
+//!    warn = 14 # days before the limit to start listing entries
+//!    entries = { github = 30 } # overrides for single entries
+//!    ```
+//!  - rotate-entries: regenerates the password fields (or the `--field` given) of every entry
+//!    matching `--filter`, a glob on the entry names like `shop-*` or `tag:<tag>`. Each field
+//!    uses the spec it was generated with, or the configured one. The vault is backed up first
+//!    and all the entries are saved in one transaction, then a checklist of the entries and
+//!    their URLs is printed to go change the passwords on the sites
+//!  - audit: checks the password fields of every vault, or the one given, for passwords reused
+//!    across entries and vaults, weak passwords, passwords not satisfying the password spec and
+//!    passwords unchanged past the vault's age policy (or `--max-age` days). The report is text or
+//!    `--format json` and names the fields without printing their values. `--breached <file>`
+//!    also looks every password up in a local copy of the Have I Been Pwned SHA-1 list (the
+//!    version ordered by hash), using a binary search so multi-GB lists are fine. Nothing is sent
+//!    over the network
+//!  - search: lists the entries and fields of a vault whose values contain the query, like a
+//!    username or URL. Only non-secret fields are searched unless `--include-secrets` is given,
+//!    and values are never printed
+//!  - find-url: lists the entries with a URL field for a site, like `accounts.google.com`. URLs
+//!    of the same origin come first, then the same host, parent domains (an entry for
+//!    `google.com`) and other hosts of the same domain (`mail.google.com`). Only the stored URLs
+//!    are read, vaults hiding their metadata are searched when given as the vault. Other programs
+//!    can use [url::Site] and [info::Info::find_url] for the same lookups
+//!  - attach: `add`, `get`, `rm` and `list` files attached to an entry, like recovery codes or
+//!    keys. Each attachment is encrypted with the vault key in its own file and is carried along
+//!    by backups, exports, rotations and moves. `attachment_limits` in `pants/pants.toml` caps
+//!    the size of one attachment and of all the attachments in a vault
 
 use secrecy::Secret;
 pub mod action;
+pub mod attachment;
+pub mod audit;
+pub mod breach;
+pub mod client;
 pub mod command;
 pub mod config;
+pub mod doctor;
 pub mod errors;
 pub mod file;
+pub mod fuzzy;
 pub mod info;
 pub mod manager_message;
 pub mod message;
+pub mod migration;
 pub mod operation;
 pub mod output;
+pub mod passphrase;
+pub mod policy;
 pub mod reads;
+pub mod rotation;
 pub mod schema;
 pub mod secure;
 pub mod store;
+pub mod strength;
+pub mod template;
+pub mod totp;
+pub mod url;
 pub mod utils;
 pub mod vault;
 
@@ -1,4 +1,4 @@
-use crate::{message::Message, Password};
+use crate::{audit::AuditSettings, doctor::Problem, message::Message, Password};
 
 /// the messages to the manager of all the vaults
 #[derive(Debug)]
@@ -7,7 +7,29 @@ pub enum ManagerMessage {
     NewVault(String),
     DeleteVault(String, Password),
     DeleteEmptyVault(String),
+    /// rename a vault from the first name to the second
+    RenameVault(String, String),
+    /// add the first name as an alias for the second
+    AddAlias(String, String),
+    RemoveAlias(String),
+    SetDefaultVault(String),
+    /// whether a vault keeps its tags and folders out of the schema
+    HideMetadata(String, bool),
+    /// whether a vault writes the values of its public fields to the schema
+    PublishFields(String, bool),
+    /// check the vaults on disk for problems
+    Doctor,
+    /// apply the fix for a problem, replaying transactions needs the vault's password
+    Repair(Problem, Option<Password>),
     List,
     Info,
     VaultMessage(String, Message),
+    /// move entries between vaults as (source, source password, destination, destination
+    /// password, keys)
+    MoveEntries(String, Password, String, Password, Vec<String>),
+    /// copy entries between vaults, same layout as [ManagerMessage::MoveEntries]
+    CopyEntries(String, Password, String, Password, Vec<String>),
+    /// check the passwords of the vaults, given with their passwords, for reuse across all of
+    /// them, weakness, spec violations and age
+    Audit(Vec<(String, Password)>, AuditSettings),
 }
@@ -1,13 +1,15 @@
-use std::collections::HashMap;
+use pants_gen::password::PasswordSpec;
 
 use crate::{
+    attachment::{Attachment, Export},
     file::BackupFile,
+    rotation::EntryFilter,
     store::{Changes, Store},
     Password,
 };
 
 /// messages that are used to send to a particular vault
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub enum Message {
     Get(Password, String),
     Update(Password, String, Store),
@@ -18,7 +20,43 @@ pub enum Message {
     Restore(Password, Password, BackupFile),
     Rename(Password, String, String),
     Export(Password),
-    Import(Password, HashMap<String, Store>),
+    Import(Password, Export),
+    /// attach a file to an entry as (entry, attachment name, contents)
+    Attach(Password, String, String, Attachment),
+    /// read an attachment as (entry, attachment name)
+    GetAttachment(Password, String, String),
+    /// remove an attachment as (entry, attachment name)
+    Detach(Password, String, String),
+    /// list the attachments of an entry
+    ListAttachments(Password, String),
+    /// change the tags of an entry as (entry, tags to add, tags to remove)
+    Tag(Password, String, Vec<String>, Vec<String>),
+    /// move an entry into a folder, an empty path moves it out of any folder
+    Folder(Password, String, String),
+    /// the schema including tags and folders, for vaults that hide them
+    Metadata(Password),
+    /// entries and fields whose values contain the query, secret fields are only searched when
+    /// the flag is set
+    Search(Password, String, bool),
+    /// run the steps as one transaction, either all of them are saved or none are, the output has
+    /// the values of the `Get` steps as of that step
+    Batch(Password, Vec<BatchStep>),
+    /// regenerate a field, or the password fields, of the matching entries as (filter, field,
+    /// spec for fields that don't remember one)
+    RotateEntries(Password, EntryFilter, Option<String>, PasswordSpec),
     Schema,
     BackupList,
+    /// remove all but the given number of most recent backups
+    PruneBackups(usize),
+}
+
+/// a step of a [Message::Batch], each step sees what the ones before it did
+#[derive(Debug, Clone)]
+pub enum BatchStep {
+    Get(String),
+    Update(String, Store),
+    Change(String, Changes),
+    /// rename an entry as (from, to)
+    Rename(String, String),
+    Delete(String),
 }
@@ -0,0 +1,73 @@
+//! layouts of encrypted data written by older versions, kept so their vaults can still be opened
+
+/// before stores had field kinds
+pub mod v1 {
+    use std::collections::BTreeMap;
+
+    use serde::Deserialize;
+
+    use crate::{action, store, store::SecretValue, vault};
+
+    #[derive(Deserialize)]
+    pub struct Store {
+        data: Vec<(String, SecretValue)>,
+    }
+
+    impl From<Store> for store::Store {
+        fn from(value: Store) -> Self {
+            store::Store::inferred(value.data)
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct Vault {
+        data: BTreeMap<String, Store>,
+    }
+
+    impl From<Vault> for vault::Vault {
+        fn from(value: Vault) -> Self {
+            value
+                .data
+                .into_iter()
+                .map(|(k, v)| (k, v.into()))
+                .collect::<BTreeMap<_, _>>()
+                .into()
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub enum Action {
+        Replace {
+            key: String,
+            start: Option<Store>,
+            end: Option<Store>,
+        },
+        Noop,
+    }
+
+    impl From<Action> for action::Action {
+        fn from(value: Action) -> Self {
+            match value {
+                Action::Replace { key, start, end } => Self::Replace {
+                    key,
+                    start: start.map(Into::into),
+                    end: end.map(Into::into),
+                },
+                Action::Noop => Self::Noop,
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct Record {
+        actions: Vec<Action>,
+    }
+
+    impl From<Record> for action::Record {
+        fn from(value: Record) -> Self {
+            Self {
+                actions: value.actions.into_iter().map(Into::into).collect(),
+            }
+        }
+    }
+}
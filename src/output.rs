@@ -1,6 +1,18 @@
+use std::{collections::BTreeMap, sync::Arc};
+
 use boring_derive::From;
 
-use crate::{file::BackupFile, info::Info, reads::Reads, schema::Schema, store::Store};
+use crate::{
+    attachment::{Attachment, AttachmentInfo},
+    audit::Audit,
+    doctor::Problem,
+    file::BackupFile,
+    info::Info,
+    reads::Reads,
+    rotation::Rotation,
+    schema::Schema,
+    store::Store,
+};
 
 #[derive(Debug, Clone, From)]
 pub enum Output {
@@ -11,5 +23,12 @@ pub enum Output {
     List(Vec<String>),
     Backup(BackupFile),
     Content(String),
+    Problems(Vec<Problem>),
+    Audit(Audit),
+    /// shared rather than copied when the output is cloned
+    Attachment(Arc<Attachment>),
+    /// attachments of an entry by name
+    Attachments(BTreeMap<String, AttachmentInfo>),
+    Rotation(Rotation),
     Nothing,
 }
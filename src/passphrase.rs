@@ -0,0 +1,205 @@
+//! diceware style passphrases, random words that are easier to remember and type than random
+//! characters
+
+use std::{collections::BTreeSet, fs, path::PathBuf};
+
+use rand::{rngs::OsRng, seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::PassphraseError, strength::Strength};
+
+/// short common words used when no wordlist file is configured, none is the start of another
+const BUNDLED: &str = include_str!("wordlist.txt");
+
+/// entropy the default spec aims for, six words of the EFF large list with 12.9 bits each
+const DEFAULT_BITS: f64 = 77.5;
+
+/// how to build a passphrase
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PassphraseSpec {
+    pub words: usize,
+    /// put between the words
+    pub separator: String,
+    /// start every word with a capital letter
+    pub capitalize: bool,
+    /// random digits added to the end of random words
+    pub digits: usize,
+    /// file to take the words from instead of the bundled list, one word per line, lines of the
+    /// EFF lists with the dice rolls in front work too and lines starting with `#` are skipped
+    pub wordlist: Option<PathBuf>,
+}
+
+impl Default for PassphraseSpec {
+    fn default() -> Self {
+        Self {
+            words: words_for(parse(BUNDLED).len()),
+            separator: "-".into(),
+            capitalize: false,
+            digits: 0,
+            wordlist: None,
+        }
+    }
+}
+
+impl PassphraseSpec {
+    pub fn words(mut self, words: usize) -> Self {
+        self.words = words;
+        self
+    }
+    pub fn separator(mut self, separator: String) -> Self {
+        self.separator = separator;
+        self
+    }
+    pub fn capitalize(mut self, capitalize: bool) -> Self {
+        self.capitalize = capitalize;
+        self
+    }
+    pub fn digits(mut self, digits: usize) -> Self {
+        self.digits = digits;
+        self
+    }
+    pub fn wordlist(mut self, wordlist: PathBuf) -> Self {
+        self.wordlist = Some(wordlist);
+        self
+    }
+
+    pub fn generate(&self) -> Result<String, PassphraseError> {
+        let wordlist = self.load()?;
+        let mut words: Vec<String> = (0..self.words)
+            .map(|_| {
+                let word = wordlist.choose(&mut OsRng).cloned().unwrap_or_default();
+                if self.capitalize {
+                    capitalize(&word)
+                } else {
+                    word
+                }
+            })
+            .collect();
+        if !words.is_empty() {
+            for _ in 0..self.digits {
+                let index = OsRng.gen_range(0..words.len());
+                let digit = OsRng.gen_range(0..10);
+                words[index].push_str(&digit.to_string());
+            }
+        }
+        Ok(words.join(&self.separator))
+    }
+
+    /// entropy of the passphrases the spec makes, an attacker is assumed to know the spec and
+    /// the wordlist
+    ///
+    /// Without a separator words that start another word, like `act` and `actor`, can make the
+    /// same passphrase in more than one way, so only the words that don't are counted then.
+    pub fn strength(&self) -> Result<Strength, PassphraseError> {
+        let wordlist = self.load()?;
+        let choices = if self.separator.is_empty() {
+            prefix_free(&wordlist)
+        } else {
+            wordlist.len()
+        };
+        let words = self.words as f64;
+        let mut bits = words * (choices as f64).log2();
+        if self.words > 0 {
+            bits += self.digits as f64 * (10f64.log2() + words.log2());
+        }
+        Ok(Strength {
+            bits,
+            pattern: None,
+        })
+    }
+
+    /// the different words of the wordlist
+    fn load(&self) -> Result<Vec<String>, PassphraseError> {
+        let text = match &self.wordlist {
+            Some(path) => fs::read_to_string(path)?,
+            None => BUNDLED.to_string(),
+        };
+        let words = parse(&text);
+        if words.len() < 2 {
+            return Err(PassphraseError::TooShort);
+        }
+        Ok(words.into_iter().collect())
+    }
+}
+
+/// the different words of a wordlist file
+fn parse(text: &str) -> BTreeSet<String> {
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_whitespace().last())
+        .map(|word| word.to_string())
+        .collect()
+}
+
+/// how many words picked from that many choices reach the default entropy
+fn words_for(choices: usize) -> usize {
+    (DEFAULT_BITS / (choices as f64).log2()).ceil() as usize
+}
+
+/// how many of the sorted words aren't the start of another word, a word starting others is
+/// always followed by one of them in sorted order
+fn prefix_free(words: &[String]) -> usize {
+    let prefixes = words
+        .windows(2)
+        .filter(|pair| pair[1].starts_with(pair[0].as_str()))
+        .count();
+    words.len() - prefixes
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{prefix_free, words_for, PassphraseSpec};
+
+    fn wordlist(text: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn bundled_list_is_prefix_free() {
+        let words = PassphraseSpec::default().load().unwrap();
+        assert_eq!(prefix_free(&words), words.len());
+    }
+
+    #[test]
+    fn default_strength() {
+        let strength = PassphraseSpec::default().strength().unwrap();
+        assert!(strength.bits > 77.0, "{}", strength.bits);
+    }
+
+    #[test]
+    fn default_word_count() {
+        assert_eq!(words_for(7776), 6);
+        assert_eq!(PassphraseSpec::default().words, 8);
+    }
+
+    #[test]
+    fn empty_separator_skips_prefixes() {
+        let file = wordlist("act\nactor\nbee\ncat\n");
+        let spec = PassphraseSpec::default()
+            .words(1)
+            .wordlist(file.path().into());
+        assert_eq!(spec.strength().unwrap().bits, 2.0);
+        let spec = spec.separator(String::new());
+        assert_eq!(spec.strength().unwrap().bits, 3f64.log2());
+    }
+
+    #[test]
+    fn comments_and_dice_rolls() {
+        let file = wordlist("# a list\n11111\tabacus\n11112\tabdomen\n\n");
+        let spec = PassphraseSpec::default().wordlist(file.path().into());
+        assert_eq!(spec.load().unwrap(), vec!["abacus", "abdomen"]);
+    }
+}
@@ -0,0 +1,94 @@
+//! how long entries can go unchanged before they're due for rotation
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// maximum ages of a vault's entries, in days
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgePolicy {
+    /// days every entry may go unchanged, no limit when missing
+    pub max_age: Option<u32>,
+    /// days specific entries may go unchanged, overriding `max_age`
+    pub entries: BTreeMap<String, u32>,
+    /// days before the limit that entries are reported as due soon
+    pub warn: u32,
+}
+
+impl Default for AgePolicy {
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            entries: BTreeMap::new(),
+            warn: 14,
+        }
+    }
+}
+
+impl AgePolicy {
+    pub fn max_age(&self, key: &str) -> Option<u32> {
+        self.entries.get(key).copied().or(self.max_age)
+    }
+
+    /// whether no entry has a maximum age
+    pub fn is_empty(&self) -> bool {
+        self.max_age.is_none() && self.entries.is_empty()
+    }
+
+    /// how close the entry is to its maximum age, [None] when it has none or isn't near it
+    pub fn status(
+        &self,
+        key: &str,
+        modified: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Option<AgeStatus> {
+        let max_age = i64::from(self.max_age(key)?);
+        let Some(modified) = modified else {
+            return Some(AgeStatus::Unknown);
+        };
+        let left = max_age - (now - modified).num_days();
+        if left < 0 {
+            Some(AgeStatus::Overdue(-left))
+        } else if left <= i64::from(self.warn) {
+            Some(AgeStatus::DueSoon(left))
+        } else {
+            None
+        }
+    }
+}
+
+/// how an entry stands against its maximum age
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "days", rename_all = "snake_case")]
+pub enum AgeStatus {
+    /// no modification time was recorded, the entry predates them
+    Unknown,
+    /// past the maximum age by a number of days
+    Overdue(i64),
+    /// reaches the maximum age in a number of days
+    DueSoon(i64),
+}
+
+impl Display for AgeStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "age unknown"),
+            Self::Overdue(days) => write!(f, "overdue by {}", Days(*days)),
+            Self::DueSoon(0) => write!(f, "due today"),
+            Self::DueSoon(days) => write!(f, "due in {}", Days(*days)),
+        }
+    }
+}
+
+struct Days(i64);
+
+impl Display for Days {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            1 => write!(f, "1 day"),
+            days => write!(f, "{days} days"),
+        }
+    }
+}
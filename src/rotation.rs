@@ -0,0 +1,140 @@
+//! regenerating the passwords of many entries at once, like after a breach
+
+use std::{collections::BTreeSet, fmt::Display, str::FromStr};
+
+use glob::Pattern;
+use pants_gen::password::PasswordSpec;
+use secrecy::ExposeSecret;
+
+use crate::{
+    errors::ClientError,
+    file::BackupFile,
+    store::{FieldKind, Store, StoredValue},
+};
+
+/// which entries to rotate, `tag:<tag>` for the entries with a tag and a glob on the entry names
+/// otherwise
+#[derive(Debug, Clone)]
+pub enum EntryFilter {
+    Tag(String),
+    Glob(Pattern),
+}
+
+impl FromStr for EntryFilter {
+    type Err = glob::PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("tag:") {
+            Some(tag) => Ok(Self::Tag(tag.into())),
+            None => Ok(Self::Glob(Pattern::new(s)?)),
+        }
+    }
+}
+
+impl Display for EntryFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tag(tag) => write!(f, "tag:{tag}"),
+            Self::Glob(pattern) => write!(f, "{pattern}"),
+        }
+    }
+}
+
+impl EntryFilter {
+    pub fn matches(&self, key: &str, tags: &BTreeSet<String>) -> bool {
+        match self {
+            Self::Tag(tag) => tags.contains(tag),
+            Self::Glob(pattern) => pattern.matches(key),
+        }
+    }
+}
+
+/// the fields of the entry to regenerate, the password fields when no field is named
+pub fn fields(store: &Store, field: Option<&str>) -> Vec<String> {
+    store
+        .data
+        .iter()
+        .map(|(key, _)| key)
+        .filter(|key| match field {
+            Some(field) => *key == field,
+            None => store.kind(key) == FieldKind::Password,
+        })
+        .cloned()
+        .collect()
+}
+
+/// the entry with new values for the fields, each generated with the spec it remembers or
+/// `default`
+pub fn regenerate(
+    store: &Store,
+    fields: &[String],
+    default: &PasswordSpec,
+) -> anyhow::Result<Store> {
+    let mut store = store.clone();
+    for field in fields {
+        let spec = match store.spec(field) {
+            Some(spec) => PasswordSpec::from_str(spec)?,
+            None => default.clone(),
+        };
+        let value = spec.generate().ok_or(ClientError::BadPasswordSpec)?;
+        store.insert(field, StoredValue::new(value).into());
+        store.set_spec(field, Some(spec.to_string()));
+    }
+    store.validate(fields)?;
+    store.touch();
+    Ok(store)
+}
+
+/// an entry whose fields were regenerated, with its URLs to know where to change them
+#[derive(Debug, Clone)]
+pub struct Rotated {
+    pub key: String,
+    pub fields: Vec<String>,
+    pub urls: Vec<String>,
+}
+
+impl Rotated {
+    pub fn new(key: &str, fields: Vec<String>, store: &Store) -> Self {
+        let urls = store
+            .data
+            .iter()
+            .filter(|(field, _)| store.kind(field) == FieldKind::Url)
+            .map(|(_, value)| value.expose_secret().to_string())
+            .collect();
+        Self {
+            key: key.into(),
+            fields,
+            urls,
+        }
+    }
+}
+
+/// result of a bulk rotation, printed as a checklist of the entries to update on their sites
+#[derive(Debug, Clone)]
+pub struct Rotation {
+    /// backup of the vault from before the rotation
+    pub backup: BackupFile,
+    pub entries: Vec<Rotated>,
+}
+
+impl Display for Rotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries = match self.entries.len() {
+            1 => "1 entry".to_string(),
+            n => format!("{n} entries"),
+        };
+        writeln!(
+            f,
+            "Regenerated {entries}, the old values are in {}",
+            self.backup
+        )?;
+        for entry in &self.entries {
+            write!(f, "[ ] {}: {}", entry.key, entry.fields.join(", "))?;
+            if !entry.urls.is_empty() {
+                write!(f, " ({})", entry.urls.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -1,28 +1,240 @@
-use std::{collections::BTreeMap, fmt::Display};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
 
-use boring_derive::From;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default, From)]
+use crate::{
+    policy::{AgePolicy, AgeStatus},
+    store::{in_folder, FieldKind, Store, StoredValue},
+    strength::StrengthPolicy,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Schema {
     pub data: BTreeMap<String, Vec<String>>,
+    /// kinds of the fields, missing from schemas written before kinds existed
+    #[serde(default)]
+    pub kinds: BTreeMap<String, BTreeMap<String, FieldKind>>,
+    /// password specs of the fields that have one, see [Store::specs]
+    #[serde(default)]
+    pub specs: BTreeMap<String, BTreeMap<String, String>>,
+    /// tags of the entries that have any
+    #[serde(default)]
+    pub tags: BTreeMap<String, BTreeSet<String>>,
+    /// folders of the entries that are in one
+    #[serde(default)]
+    pub folders: BTreeMap<String, String>,
+    /// values of the public fields, see [FieldKind::is_public], only for vaults that publish them
+    #[serde(default)]
+    pub public: BTreeMap<String, BTreeMap<String, String>>,
+    /// when the values of the entries last changed, for the ones where it's known
+    #[serde(default)]
+    pub modified: BTreeMap<String, DateTime<Utc>>,
+    /// maximum ages of the entries, filled in from the configuration when the schema is read
+    #[serde(default, skip_serializing_if = "AgePolicy::is_empty")]
+    pub policy: AgePolicy,
+    /// how strong manually entered passwords have to be, filled in from the configuration like
+    /// `policy`
+    #[serde(default, skip_serializing_if = "StrengthPolicy::is_default")]
+    pub strength: StrengthPolicy,
+    /// tags, folders, public values and modification times were left out because the vault hides
+    /// its metadata
+    #[serde(default)]
+    pub hidden: bool,
+    /// public values were left out because the vault doesn't publish them or hides its metadata
+    #[serde(default)]
+    pub unpublished: bool,
 }
 
-impl Schema {
-    pub fn new() -> Self {
+impl From<BTreeMap<String, Vec<String>>> for Schema {
+    fn from(data: BTreeMap<String, Vec<String>>) -> Self {
         Self {
-            data: BTreeMap::new(),
+            data,
+            ..Default::default()
         }
     }
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
     pub fn insert(&mut self, key: String, value: Vec<String>) {
         self.data.insert(key, value);
     }
 
+    pub fn insert_kinds(&mut self, key: String, kinds: BTreeMap<String, FieldKind>) {
+        self.kinds.insert(key, kinds);
+    }
+
+    pub fn insert_specs(&mut self, key: String, specs: BTreeMap<String, String>) {
+        if !specs.is_empty() {
+            self.specs.insert(key, specs);
+        }
+    }
+
+    pub fn insert_tags(&mut self, key: String, tags: BTreeSet<String>) {
+        if !tags.is_empty() {
+            self.tags.insert(key, tags);
+        }
+    }
+
+    pub fn insert_folder(&mut self, key: String, folder: Option<String>) {
+        if let Some(folder) = folder {
+            self.folders.insert(key, folder);
+        }
+    }
+
+    pub fn insert_public(&mut self, key: String, values: BTreeMap<String, String>) {
+        if !values.is_empty() {
+            self.public.insert(key, values);
+        }
+    }
+
+    pub fn insert_modified(&mut self, key: String, modified: Option<DateTime<Utc>>) {
+        if let Some(modified) = modified {
+            self.modified.insert(key, modified);
+        }
+    }
+
+    /// how close the entry is to its maximum age, [None] when it isn't near it or the vault hides
+    /// when entries were modified
+    pub fn age_status(&self, key: &str, now: DateTime<Utc>) -> Option<AgeStatus> {
+        if self.hidden {
+            return None;
+        }
+        self.policy
+            .status(key, self.modified.get(key).copied(), now)
+    }
+
+    /// entries past or near their maximum age
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<(&String, AgeStatus)> {
+        self.data
+            .keys()
+            .filter_map(|key| Some((key, self.age_status(key, now)?)))
+            .collect()
+    }
+
+    /// value of a field readable without the password
+    pub fn public(&self, key: &str, field: &str) -> Option<&str> {
+        self.public.get(key)?.get(field).map(|value| value.as_str())
+    }
+
+    pub fn tags(&self, key: &str) -> BTreeSet<String> {
+        self.tags.get(key).cloned().unwrap_or_default()
+    }
+
+    pub fn folder(&self, key: &str) -> Option<&str> {
+        self.folders.get(key).map(|folder| folder.as_str())
+    }
+
+    /// leave out the values of the public fields so they can't be read without the password
+    pub fn unpublish(&mut self) {
+        self.public.clear();
+        self.unpublished = true;
+    }
+
+    /// leave out the tags, folders, public values and modification times so they can't be read
+    /// without the password
+    pub fn hide_metadata(&mut self) {
+        self.tags.clear();
+        self.folders.clear();
+        self.unpublish();
+        self.modified.clear();
+        self.hidden = true;
+    }
+
+    /// entries that have all the tags and are in the folder or one of its subfolders
+    pub fn filter(&self, tags: &[String], folder: Option<&str>) -> Schema {
+        let mut schema = self.clone();
+        schema.data.retain(|key, _| {
+            let entry_tags = self.tags.get(key);
+            tags.iter()
+                .all(|tag| entry_tags.is_some_and(|entry_tags| entry_tags.contains(tag)))
+                && folder.map_or(true, |folder| in_folder(self.folder(key), folder))
+        });
+        schema
+    }
+
+    /// the entries grouped by folder, entries outside of any folder come first under [None]
+    pub fn grouped(&self) -> BTreeMap<Option<&str>, Vec<&String>> {
+        let mut groups: BTreeMap<Option<&str>, Vec<&String>> = BTreeMap::new();
+        for key in self.data.keys() {
+            groups.entry(self.folder(key)).or_default().push(key);
+        }
+        groups
+    }
+
+    /// write the entries grouped by folder with their tags and fields, indented by `indent`
+    pub fn write_entries(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        indent: usize,
+    ) -> std::fmt::Result {
+        for (folder, keys) in self.grouped() {
+            let indent = match folder {
+                Some(folder) => {
+                    writeln!(f, "{:indent$}{folder}/", "")?;
+                    indent + 1
+                }
+                None => indent,
+            };
+            for key in keys {
+                let tags = self.tags(key);
+                if tags.is_empty() {
+                    writeln!(f, "{:indent$}{key}:", "")?;
+                } else {
+                    let tags: Vec<&str> = tags.iter().map(|tag| tag.as_str()).collect();
+                    writeln!(f, "{:indent$}{key}: [{}]", "", tags.join(", "))?;
+                }
+                for field in self.get(key).into_iter().flatten() {
+                    let kind = self.kind(key, field);
+                    match self.public(key, field) {
+                        Some(value) => writeln!(f, "{:indent$} - {field} ({kind}): {value}", "")?,
+                        None => writeln!(f, "{:indent$} - {field} ({kind})", "")?,
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn get(&self, key: &str) -> Option<&Vec<String>> {
         self.data.get(key)
     }
 
+    pub fn kind(&self, key: &str, field: &str) -> FieldKind {
+        self.kinds
+            .get(key)
+            .and_then(|kinds| kinds.get(field))
+            .copied()
+            .unwrap_or_else(|| FieldKind::infer(field))
+    }
+
+    /// the spec a field was generated with
+    pub fn spec(&self, key: &str, field: &str) -> Option<&String> {
+        self.specs.get(key)?.get(field)
+    }
+
+    /// entry with the fields of `key` and empty values, a stand-in until the values are read
+    pub fn blank(&self, key: &str) -> Option<Store> {
+        let mut store = Store {
+            tags: self.tags(key),
+            folder: self.folder(key).map(|folder| folder.to_string()),
+            ..Default::default()
+        };
+        for field in self.get(key)? {
+            let value = self.public(key, field).unwrap_or_default();
+            store.insert_kind(field, self.kind(key, field), StoredValue::new(value).into());
+            store.set_spec(field, self.spec(key, field).cloned());
+        }
+        Some(store)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
     }
@@ -50,12 +262,6 @@ impl IntoIterator for Schema {
 
 impl Display for Schema {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (key, value) in self.data.iter() {
-            writeln!(f, "{key}:")?;
-            for field in value {
-                writeln!(f, " - {field}")?;
-            }
-        }
-        Ok(())
+        self.write_entries(f, 0)
     }
 }
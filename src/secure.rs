@@ -6,7 +6,7 @@ use aes_gcm::{
 };
 use argon2::{password_hash::SaltString, Argon2};
 use secrecy::ExposeSecret;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
     errors::{DecryptionError, EncryptionError},
@@ -26,9 +26,27 @@ pub struct Decrypted<Data> {
     data_type: PhantomData<Data>,
 }
 
-impl<'de, Data: Deserialize<'de>> Decrypted<Data> {
-    pub fn deserialize(&'de self) -> Data {
-        bincode::deserialize(&self.data).unwrap()
+/// prefixed to the plaintext before encrypting, data without it was written by an older version
+///
+/// What follows the marker is MessagePack with the field names kept, so a field added later with
+/// `#[serde(default)]` reads from data written before it and doesn't need a new marker.
+const FORMAT_MARKER: &[u8; 8] = b"pants\0v2";
+
+/// encrypted data whose layout has changed between versions
+pub trait Versioned: DeserializeOwned {
+    /// bincode layout written before the format marker was introduced
+    type Legacy: DeserializeOwned + Into<Self>;
+}
+
+impl<Data: Versioned> Decrypted<Data> {
+    pub fn deserialize(&self) -> Data {
+        if let Some(data) = self.data.strip_prefix(FORMAT_MARKER) {
+            rmp_serde::from_slice(data).unwrap()
+        } else {
+            bincode::deserialize::<Data::Legacy>(&self.data)
+                .unwrap()
+                .into()
+        }
     }
 }
 
@@ -50,7 +68,8 @@ impl<'de, Data: Serialize + Deserialize<'de>> Encrypted<Data> {
     pub fn encrypt(data: &Data, key: Key<Aes256Gcm>) -> anyhow::Result<Encrypted<Data>> {
         let cipher = Aes256Gcm::new(&key);
         let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-        let encoded = bincode::serialize(data)?;
+        let mut encoded = FORMAT_MARKER.to_vec();
+        encoded.extend(rmp_serde::to_vec_named(data)?);
         let encrypted = cipher
             .encrypt(&nonce, encoded.as_ref())
             .map_err(|_| EncryptionError::Encryption)?;
@@ -109,3 +128,85 @@ pub trait SecureData {
         output_key.into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, marker::PhantomData};
+
+    use aes_gcm::{Aes256Gcm, Key};
+    use secrecy::{ExposeSecret, Secret};
+    use serde::{Deserialize, Serialize};
+
+    use super::{Decrypted, Encrypted, Versioned, FORMAT_MARKER};
+    use crate::{
+        store::{FieldKind, SecretValue, StoredValue},
+        vault::Vault,
+    };
+
+    #[derive(Serialize, Deserialize)]
+    struct Before {
+        name: String,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct After {
+        name: String,
+        #[serde(default)]
+        added: Vec<String>,
+    }
+
+    impl Versioned for After {
+        type Legacy = Self;
+    }
+
+    fn decrypted<Data>(data: Vec<u8>) -> Decrypted<Data> {
+        Decrypted {
+            data,
+            data_type: PhantomData,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let key = Key::<Aes256Gcm>::from([7; 32]);
+        let data = After {
+            name: "name".into(),
+            added: vec!["value".into()],
+        };
+        let read = Encrypted::encrypt(&data, key)
+            .unwrap()
+            .decrypt(key)
+            .unwrap()
+            .deserialize();
+        assert_eq!(read.name, "name");
+        assert_eq!(read.added, vec!["value".to_string()]);
+    }
+
+    #[test]
+    fn added_fields_read_older_data() {
+        let before = Before {
+            name: "name".into(),
+        };
+        let mut data = FORMAT_MARKER.to_vec();
+        data.extend(rmp_serde::to_vec_named(&before).unwrap());
+        let read: After = decrypted(data).deserialize();
+        assert_eq!(read.name, "name");
+        assert!(read.added.is_empty());
+    }
+
+    #[test]
+    fn legacy_vault() {
+        // the entries were only their fields, written with bincode and no marker
+        let fields: Vec<(String, SecretValue)> =
+            vec![("Password".into(), Secret::new(StoredValue::new("hunter2")))];
+        let entries = BTreeMap::from([("a".to_string(), fields)]);
+        let vault: Vault = decrypted(bincode::serialize(&entries).unwrap()).deserialize();
+        let (key, store) = vault.entries().next().unwrap();
+        assert_eq!(key, "a");
+        assert_eq!(
+            store.get("Password").unwrap().expose_secret().to_string(),
+            "hunter2"
+        );
+        assert_eq!(store.kind("Password"), FieldKind::Password);
+    }
+}
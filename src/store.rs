@@ -1,17 +1,333 @@
-use std::fmt::Display;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
 
 use boring_derive::From;
-use enum_iterator::Sequence;
-use secrecy::{CloneableSecret, DebugSecret, Secret, SerializableSecret, Zeroize};
+use chrono::{DateTime, Utc};
+use enum_iterator::{all, Sequence};
+use secrecy::{CloneableSecret, DebugSecret, ExposeSecret, Secret, SerializableSecret, Zeroize};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence, Serialize, Deserialize)]
-pub enum StoreType {
+use crate::{
+    errors::{FieldError, SchemaError},
+    totp::Totp,
+    Password,
+};
+
+/// known shapes of a [Store], used to edit entries through typed forms
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Sequence, Serialize, Deserialize)]
+pub enum StoreChoice {
+    #[default]
     Password,
     UsernamePassword,
+    /// anything that doesn't fit one of the other shapes
     Generic,
 }
 
+impl Display for StoreChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Password => write!(f, "Password"),
+            Self::UsernamePassword => write!(f, "Username and password"),
+            Self::Generic => write!(f, "Generic"),
+        }
+    }
+}
+
+impl StoreChoice {
+    pub fn all() -> Vec<Self> {
+        all::<Self>().collect()
+    }
+
+    /// `(hash key, field name, kind)` for each field of the shape, empty for [StoreChoice::Generic]
+    pub fn fields(&self) -> &'static [(&'static str, &'static str, FieldKind)] {
+        match self {
+            Self::Password => &[("password", "Password", FieldKind::Password)],
+            Self::UsernamePassword => &[
+                ("username", "Username", FieldKind::Username),
+                ("password", "Password", FieldKind::Password),
+            ],
+            Self::Generic => &[],
+        }
+    }
+
+    /// the shape a [Store] has, field names are compared ignoring case
+    pub fn classify(store: &Store) -> Self {
+        for choice in [Self::Password, Self::UsernamePassword] {
+            let fields = choice.fields();
+            let matches = store.data.len() == fields.len()
+                && fields.iter().all(|(_, name, kind)| {
+                    store
+                        .data
+                        .iter()
+                        .any(|(k, _)| k.eq_ignore_ascii_case(name) && store.kind(k) == *kind)
+                });
+            if matches {
+                return choice;
+            }
+        }
+        Self::Generic
+    }
+
+    /// empty [Store] of this shape
+    pub fn convert_default(&self) -> Store {
+        let mut store = Store::default();
+        for (_, name, kind) in self.fields() {
+            store.insert_kind(name, *kind, StoredValue::new("").into());
+        }
+        store
+    }
+
+    /// build a [Store] of this shape from the values in the hash
+    pub fn convert(&self, hash: &StoreHash) -> Result<Store, SchemaError> {
+        let mut store = Store::default();
+        store.tags.clone_from(&hash.tags);
+        store.folder.clone_from(&hash.folder);
+        match self {
+            Self::Generic => {
+                for (key, kind, value) in hash.fields() {
+                    store.insert_kind(key, kind, StoredValue::new(value.expose_secret()).into());
+                    store.set_spec(key, hash.specs.get(key).cloned());
+                }
+            }
+            _ => {
+                for (key, name, kind) in self.fields() {
+                    let value = hash.get(key).ok_or(SchemaError::BadValues)?;
+                    store.insert_kind(name, *kind, StoredValue::new(value.expose_secret()).into());
+                    store.set_spec(name, hash.specs.get(*key).cloned());
+                }
+            }
+        }
+        Ok(store)
+    }
+}
+
+/// values of a [Store] keyed for editing, typed shapes use the hash keys from
+/// [StoreChoice::fields] while generic entries use the field names, field order is kept
+#[derive(Debug, Clone, Default)]
+pub struct StoreHash {
+    data: Vec<(String, FieldKind, Password)>,
+    pub tags: BTreeSet<String>,
+    pub folder: Option<String>,
+    /// password specs by hash key
+    pub specs: BTreeMap<String, String>,
+}
+
+impl StoreHash {
+    pub fn get(&self, key: &str) -> Option<&Password> {
+        self.data
+            .iter()
+            .find(|(k, _, _)| k == key)
+            .map(|(_, _, v)| v)
+    }
+
+    pub fn kind(&self, key: &str) -> Option<FieldKind> {
+        self.data
+            .iter()
+            .find(|(k, _, _)| k == key)
+            .map(|(_, kind, _)| *kind)
+    }
+
+    /// set the value of a field, new fields get their kind from [FieldKind::infer]
+    pub fn insert(&mut self, key: String, value: Password) {
+        let kind = self.kind(&key).unwrap_or_else(|| FieldKind::infer(&key));
+        self.insert_kind(key, kind, value);
+    }
+
+    pub fn insert_kind(&mut self, key: String, kind: FieldKind, value: Password) {
+        match self.data.iter_mut().find(|(k, _, _)| *k == key) {
+            Some(field) => *field = (key, kind, value),
+            None => self.data.push((key, kind, value)),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Password)> {
+        self.data.iter().map(|(k, _, v)| (k, v))
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = (&String, FieldKind, &Password)> {
+        self.data.iter().map(|(k, kind, v)| (k, *kind, v))
+    }
+
+    /// value of the first password field
+    pub fn password(&self) -> Option<&Password> {
+        self.data
+            .iter()
+            .find(|(_, kind, _)| *kind == FieldKind::Password)
+            .map(|(_, _, v)| v)
+    }
+
+    /// spec of the first password field
+    pub fn password_spec(&self) -> Option<&String> {
+        let (key, _, _) = self
+            .data
+            .iter()
+            .find(|(_, kind, _)| *kind == FieldKind::Password)?;
+        self.specs.get(key)
+    }
+
+    /// remember or forget the spec of the first password field, false if there is none
+    pub fn set_password_spec(&mut self, spec: Option<String>) -> bool {
+        let Some((key, _, _)) = self
+            .data
+            .iter()
+            .find(|(_, kind, _)| *kind == FieldKind::Password)
+        else {
+            return false;
+        };
+        match spec {
+            Some(spec) => self.specs.insert(key.clone(), spec),
+            None => self.specs.remove(key),
+        };
+        true
+    }
+
+    /// replace the value of the first password field, false if there is none
+    pub fn set_password(&mut self, value: Password) -> bool {
+        match self
+            .data
+            .iter_mut()
+            .find(|(_, kind, _)| *kind == FieldKind::Password)
+        {
+            Some(field) => {
+                field.2 = value;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// what kind of value a field of a [Store] holds
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Sequence, Serialize, Deserialize,
+)]
+pub enum FieldKind {
+    Password,
+    Username,
+    Url,
+    Email,
+    Note,
+    Totp,
+    Date,
+    Number,
+    #[default]
+    Generic,
+}
+
+impl Display for FieldKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Password => "password",
+            Self::Username => "username",
+            Self::Url => "URL",
+            Self::Email => "email",
+            Self::Note => "note",
+            Self::Totp => "TOTP seed",
+            Self::Date => "date",
+            Self::Number => "number",
+            Self::Generic => "generic",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FieldKind {
+    /// guess the kind of a field from its name, used for fields stored before kinds existed
+    pub fn infer(name: &str) -> Self {
+        let name = name.trim().to_lowercase().replace(['-', '_', ' '], "");
+        match name.as_str() {
+            "password" | "pass" | "passphrase" | "pin" | "secret" => Self::Password,
+            "username" | "user" | "login" | "account" => Self::Username,
+            "url" | "website" | "site" | "link" | "homepage" => Self::Url,
+            "email" | "mail" | "emailaddress" => Self::Email,
+            "note" | "notes" | "comment" | "comments" | "description" => Self::Note,
+            "totp" | "otp" | "2fa" | "mfa" | "totpseed" | "authenticator" => Self::Totp,
+            "date" | "expiry" | "expires" | "expiration" | "birthday" => Self::Date,
+            "number" | "phone" | "phonenumber" => Self::Number,
+            _ => Self::Generic,
+        }
+    }
+
+    /// whether values of this kind should be hidden when shown to the user
+    pub fn is_secret(&self) -> bool {
+        matches!(self, Self::Password | Self::Totp | Self::Generic)
+    }
+
+    /// whether values of this kind are written to the schema so they can be read without the
+    /// vault password
+    pub fn is_public(&self) -> bool {
+        matches!(self, Self::Username | Self::Url | Self::Email)
+    }
+
+    /// check that a value makes sense for this kind
+    pub fn is_valid(&self, value: &str) -> bool {
+        match self {
+            Self::Password | Self::Username => !value.is_empty(),
+            Self::Url => valid_url(value),
+            Self::Email => valid_email(value),
+            Self::Totp => value.parse::<Totp>().is_ok(),
+            Self::Date => valid_date(value),
+            Self::Number => valid_number(value),
+            Self::Note | Self::Generic => true,
+        }
+    }
+
+    pub fn validate(&self, field: &str, value: &str) -> Result<(), FieldError> {
+        if self.is_valid(value) {
+            Ok(())
+        } else {
+            Err(FieldError::Invalid(field.to_string(), *self))
+        }
+    }
+}
+
+fn valid_url(value: &str) -> bool {
+    let Some((scheme, rest)) = value.trim().split_once("://") else {
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    !scheme.is_empty()
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !host.is_empty()
+        && !host.contains(char::is_whitespace)
+}
+
+fn valid_email(value: &str) -> bool {
+    let value = value.trim();
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !value.contains(char::is_whitespace)
+}
+
+/// a full date like `2027-12-31`, or a month like `12/27` or `12/2027` as cards print expiry
+/// dates
+fn valid_date(value: &str) -> bool {
+    let value = value.trim();
+    let month = format!("01/{value}");
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+        || ["%d/%m/%y", "%d/%m/%Y"]
+            .iter()
+            .any(|format| chrono::NaiveDate::parse_from_str(&month, format).is_ok())
+}
+
+fn valid_number(value: &str) -> bool {
+    let value = value.trim();
+    value.chars().any(|c| c.is_ascii_digit())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, ' ' | '-' | '+' | '.' | ',' | '(' | ')'))
+}
+
 #[derive(Clone, Serialize, Deserialize, From)]
 pub struct StoredValue(String);
 
@@ -44,6 +360,21 @@ pub type SecretValue = Secret<StoredValue>;
 pub struct Store {
     // pub ty: StoreType,
     pub data: Vec<(String, SecretValue)>,
+    /// fields without a kind here fall back to [FieldKind::infer]
+    #[serde(default)]
+    pub kinds: BTreeMap<String, FieldKind>,
+    /// labels for finding and grouping entries
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    /// slash separated path of the folder the entry is in, see [normalize_folder]
+    #[serde(default)]
+    pub folder: Option<String>,
+    /// when the values were last changed, missing for entries written before it was recorded
+    #[serde(default)]
+    pub modified: Option<DateTime<Utc>>,
+    /// password specs the fields were generated with, reused when regenerating them
+    #[serde(default)]
+    pub specs: BTreeMap<String, String>,
 }
 
 impl Store {
@@ -52,6 +383,8 @@ impl Store {
         Self {
             // ty: StoreType::Password,
             data: vec![("Password".into(), pass.into())],
+            kinds: BTreeMap::from([("Password".into(), FieldKind::Password)]),
+            ..Default::default()
         }
     }
     pub fn username_password(
@@ -64,13 +397,105 @@ impl Store {
                 ("Username".into(), username.into()),
                 ("Password".into(), pass.into()),
             ],
+            kinds: BTreeMap::from([
+                ("Username".into(), FieldKind::Username),
+                ("Password".into(), FieldKind::Password),
+            ]),
+            ..Default::default()
         }
     }
     pub fn new(data: impl Into<Vec<(String, SecretValue)>>) -> Self {
         Self {
             // ty,
             data: data.into(),
+            ..Default::default()
+        }
+    }
+
+    /// store with the kind of every field inferred from its name
+    pub fn inferred(data: impl Into<Vec<(String, SecretValue)>>) -> Self {
+        let mut store = Self::new(data);
+        store.kinds = store
+            .data
+            .iter()
+            .map(|(k, _)| (k.clone(), FieldKind::infer(k)))
+            .collect();
+        store
+    }
+
+    pub fn kind(&self, key: &str) -> FieldKind {
+        self.kinds
+            .get(key)
+            .copied()
+            .unwrap_or_else(|| FieldKind::infer(key))
+    }
+
+    pub fn set_kind(&mut self, key: &str, kind: FieldKind) {
+        self.kinds.insert(key.into(), kind);
+    }
+
+    pub fn spec(&self, key: &str) -> Option<&String> {
+        self.specs.get(key)
+    }
+
+    /// remember the spec a field is generated with, [None] forgets it
+    pub fn set_spec(&mut self, key: &str, spec: Option<String>) {
+        match spec {
+            Some(spec) => self.specs.insert(key.into(), spec),
+            None => self.specs.remove(key),
+        };
+    }
+
+    /// mark the values as changed now
+    pub fn touch(&mut self) {
+        self.modified = Some(Utc::now());
+    }
+
+    /// move the entry into a folder, an empty path moves it out of any folder
+    pub fn set_folder(&mut self, path: &str) {
+        self.folder = normalize_folder(path);
+    }
+
+    /// whether the entry is in the folder or one of its subfolders
+    pub fn in_folder(&self, path: &str) -> bool {
+        in_folder(self.folder.as_deref(), path)
+    }
+
+    pub fn insert_kind(&mut self, key: &str, kind: FieldKind, value: SecretValue) {
+        self.set_kind(key, kind);
+        self.insert(key, value);
+    }
+
+    /// check the given fields against their kinds
+    pub fn validate(&self, fields: &[String]) -> Result<(), FieldError> {
+        for field in fields {
+            if let Some(value) = self.get(field) {
+                self.kind(field)
+                    .validate(field, &value.expose_secret().to_string())?;
+            }
         }
+        Ok(())
+    }
+
+    /// the fields whose value or kind isn't what the entry had before, every field of a new entry
+    pub fn changed_from(&self, before: Option<&Store>) -> Vec<String> {
+        self.data
+            .iter()
+            .filter(|(key, value)| match before {
+                Some(before) => {
+                    before.kind(key) != self.kind(key)
+                        || before.get(key).map(|old| old.expose_secret().to_string())
+                            != Some(value.expose_secret().to_string())
+                }
+                None => true,
+            })
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    pub fn validate_all(&self) -> Result<(), FieldError> {
+        let fields: Vec<String> = self.data.iter().map(|(k, _)| k.to_string()).collect();
+        self.validate(&fields)
     }
 
     pub fn insert(&mut self, key: &str, value: SecretValue) {
@@ -101,20 +526,69 @@ impl Store {
 
     pub fn remove(&mut self, key: &str) -> Option<(String, SecretValue)> {
         let pos = self.data.iter().position(|(k, _)| k == key)?;
+        self.kinds.remove(key);
+        self.specs.remove(key);
         Some(self.data.remove(pos))
     }
 
+    /// the shape of the store and its values keyed for editing
+    pub fn split(&self) -> (StoreChoice, StoreHash) {
+        (StoreChoice::classify(self), self.as_hash())
+    }
+
+    pub fn as_hash(&self) -> StoreHash {
+        let choice = StoreChoice::classify(self);
+        let mut hash = StoreHash {
+            tags: self.tags.clone(),
+            folder: self.folder.clone(),
+            ..Default::default()
+        };
+        for (k, v) in &self.data {
+            let value: Password = v.expose_secret().to_string().into();
+            let key = choice
+                .fields()
+                .iter()
+                .find(|(_, name, _)| k.eq_ignore_ascii_case(name))
+                .map(|(key, _, _)| key.to_string())
+                .unwrap_or_else(|| k.to_string());
+            if let Some(spec) = self.spec(k) {
+                hash.specs.insert(key.clone(), spec.clone());
+            }
+            hash.insert_kind(key, self.kind(k), value);
+        }
+        hash
+    }
+
     pub fn join(&mut self, extension: Store) {
         for (k, v) in extension.data {
+            if let Some(kind) = extension.kinds.get(&k) {
+                self.set_kind(&k, *kind);
+            }
+            if let Some(spec) = extension.specs.get(&k) {
+                self.set_spec(&k, Some(spec.clone()));
+            }
             self.insert(&k, v);
         }
     }
 
     pub fn update(&self, changes: Changes) -> Self {
-        let mut new = Self::default();
+        let mut new = Self {
+            tags: self.tags.clone(),
+            folder: self.folder.clone(),
+            modified: self.modified,
+            ..Default::default()
+        };
         for (k, v) in changes.data {
             let value = v.or_else(|| self.get(&k).cloned());
             if let Some(value) = value {
+                if let Some(kind) = changes.kinds.get(&k).or_else(|| self.kinds.get(&k)) {
+                    new.set_kind(&k, *kind);
+                }
+                let spec = match changes.specs.get(&k) {
+                    Some(spec) => spec.clone(),
+                    None => self.spec(&k).cloned(),
+                };
+                new.set_spec(&k, spec);
                 new.insert(&k, value);
             }
         }
@@ -122,22 +596,70 @@ impl Store {
     }
 }
 
+/// trim the slashes and empty segments from a folder path, [None] when nothing is left
+pub fn normalize_folder(path: &str) -> Option<String> {
+    let segments: Vec<&str> = path
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    (!segments.is_empty()).then(|| segments.join("/"))
+}
+
+/// whether `folder` is the folder at `path` or one of its subfolders
+pub fn in_folder(folder: Option<&str>, path: &str) -> bool {
+    match (folder, normalize_folder(path)) {
+        (_, None) => true,
+        (None, Some(_)) => false,
+        (Some(folder), Some(path)) => {
+            folder == path
+                || folder
+                    .strip_prefix(&path)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        }
+    }
+}
+
 /// represents the changes during an update to the [Store]
 #[derive(Debug, Clone)]
 pub struct Changes {
     data: Vec<(String, Option<SecretValue>)>,
+    kinds: BTreeMap<String, FieldKind>,
+    /// new specs of fields, [None] forgets the spec
+    specs: BTreeMap<String, Option<String>>,
 }
 
 impl Changes {
     pub fn new(fields: &[String]) -> Self {
         let data = fields.iter().map(|s| (s.into(), None)).collect();
-        Self { data }
+        Self {
+            data,
+            kinds: BTreeMap::new(),
+            specs: BTreeMap::new(),
+        }
     }
 
     pub fn fields(&self) -> Vec<String> {
         self.data.iter().map(|(k, _)| k.to_string()).collect()
     }
 
+    /// fields that are given a new value
+    pub fn changed(&self) -> Vec<String> {
+        self.data
+            .iter()
+            .filter(|(_, v)| v.is_some())
+            .map(|(k, _)| k.to_string())
+            .collect()
+    }
+
+    pub fn set_kind(&mut self, key: &str, kind: FieldKind) {
+        self.kinds.insert(key.into(), kind);
+    }
+
+    pub fn set_spec(&mut self, key: &str, spec: Option<String>) {
+        self.specs.insert(key.into(), spec);
+    }
+
     pub fn insert(&mut self, key: &str, value: SecretValue) {
         if let Some(v) = self.get_mut(key) {
             *v = Some(value)
@@ -165,6 +687,8 @@ impl Changes {
     }
     pub fn remove(&mut self, key: &str) -> Option<(String, Option<SecretValue>)> {
         let pos = self.data.iter().position(|(k, _)| k == key)?;
+        self.kinds.remove(key);
+        self.specs.remove(key);
         Some(self.data.remove(pos))
     }
     pub fn swap(&mut self, first: &str, second: &str) {
@@ -197,10 +721,13 @@ impl Changes {
     }
 
     pub fn unchanged(&self, fields: &[String]) -> bool {
+        if !self.kinds.is_empty() || !self.specs.is_empty() {
+            return false;
+        }
         let mut curr = Vec::new();
         for (k, v) in &self.data {
             if v.is_some() {
-                return true;
+                return false;
             }
             curr.push(k.to_string());
         }
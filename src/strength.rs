@@ -0,0 +1,529 @@
+//! rough estimate of how hard a password is to guess
+//!
+//! The estimate starts from the entropy of picking each character at random from the character
+//! classes the password uses, then looks for the parts an attacker would guess long before that:
+//! common passwords and words (with the usual letter substitutions undone), repeated characters,
+//! sequences, runs of neighbouring keys and years. Each part found is charged what it takes to
+//! guess it instead of its characters, picking the cheapest way to cover the whole password.
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// passwords estimated below this many bits are considered weak
+pub const WEAK_BITS: f64 = 50.0;
+
+/// common passwords and words, most common first
+const COMMON: &[&str] = &[
+    "123456",
+    "password",
+    "12345678",
+    "qwerty",
+    "123456789",
+    "12345",
+    "1234",
+    "111111",
+    "1234567",
+    "dragon",
+    "123123",
+    "baseball",
+    "abc123",
+    "football",
+    "monkey",
+    "letmein",
+    "696969",
+    "shadow",
+    "master",
+    "666666",
+    "qwertyuiop",
+    "123321",
+    "mustang",
+    "1234567890",
+    "michael",
+    "654321",
+    "superman",
+    "1qaz2wsx",
+    "7777777",
+    "121212",
+    "000000",
+    "qazwsx",
+    "123qwe",
+    "killer",
+    "trustno1",
+    "jordan",
+    "jennifer",
+    "zxcvbnm",
+    "asdfgh",
+    "hunter",
+    "buster",
+    "soccer",
+    "harley",
+    "batman",
+    "andrew",
+    "tigger",
+    "sunshine",
+    "iloveyou",
+    "charlie",
+    "robert",
+    "thomas",
+    "hockey",
+    "ranger",
+    "daniel",
+    "starwars",
+    "klaster",
+    "112233",
+    "george",
+    "computer",
+    "michelle",
+    "jessica",
+    "pepper",
+    "zxcvbn",
+    "555555",
+    "131313",
+    "freedom",
+    "777777",
+    "pass",
+    "maggie",
+    "159753",
+    "aaaaaa",
+    "ginger",
+    "princess",
+    "joshua",
+    "cheese",
+    "amanda",
+    "summer",
+    "love",
+    "ashley",
+    "nicole",
+    "chelsea",
+    "biteme",
+    "matthew",
+    "access",
+    "yankees",
+    "987654321",
+    "dallas",
+    "austin",
+    "thunder",
+    "taylor",
+    "matrix",
+    "welcome",
+    "admin",
+    "login",
+    "secret",
+    "hello",
+    "flower",
+    "passw0rd",
+    "solo",
+    "whatever",
+    "qwerty123",
+    "google",
+    "samsung",
+    "pokemon",
+    "orange",
+    "banana",
+    "apple",
+    "chocolate",
+    "cookie",
+    "winter",
+    "spring",
+    "autumn",
+    "purple",
+    "silver",
+    "golden",
+    "diamond",
+    "phoenix",
+    "tiger",
+    "lion",
+    "eagle",
+    "falcon",
+    "angel",
+    "devil",
+    "heaven",
+    "money",
+    "family",
+    "friend",
+    "happy",
+    "lucky",
+    "magic",
+    "music",
+    "player",
+    "gamer",
+    "mother",
+    "father",
+    "sister",
+    "brother",
+    "baby",
+    "kitty",
+    "puppy",
+    "house",
+    "school",
+    "office",
+    "change",
+    "default",
+    "guest",
+    "root",
+    "user",
+    "test",
+    "demo",
+    "temp",
+    "changeme",
+    "mypass",
+    "letmein1",
+    "abcdef",
+    "abcd1234",
+];
+
+/// rows of a qwerty keyboard, for runs of neighbouring keys
+const KEYBOARD_ROWS: &[&str] = &["1234567890-=", "qwertyuiop[]", "asdfghjkl;'", "zxcvbnm,./"];
+
+/// what made a password easier to guess than its length suggests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    /// a common password or word
+    Common,
+    /// the same character over and over
+    Repeat,
+    /// consecutive letters or digits like `abc` or `987`
+    Sequence,
+    /// neighbouring keys like `asdf`
+    Keyboard,
+    Year,
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Common => write!(f, "contains a common password or word"),
+            Self::Repeat => write!(f, "repeats a character"),
+            Self::Sequence => write!(f, "contains a sequence like abc or 123"),
+            Self::Keyboard => write!(f, "contains neighbouring keys like asdf"),
+            Self::Year => write!(f, "contains a year"),
+        }
+    }
+}
+
+/// how strong a password is in words
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rating {
+    VeryWeak,
+    Weak,
+    Fair,
+    Strong,
+    VeryStrong,
+}
+
+impl Display for Rating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::VeryWeak => write!(f, "very weak"),
+            Self::Weak => write!(f, "weak"),
+            Self::Fair => write!(f, "fair"),
+            Self::Strong => write!(f, "strong"),
+            Self::VeryStrong => write!(f, "very strong"),
+        }
+    }
+}
+
+/// estimated guessing entropy of a password
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize)]
+pub struct Strength {
+    pub bits: f64,
+    /// the pattern covering most of the password, if any was found
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<Pattern>,
+}
+
+/// a guessable part of a password, from `start` up to `end`
+struct Part {
+    start: usize,
+    end: usize,
+    bits: f64,
+    pattern: Pattern,
+}
+
+impl Strength {
+    /// entropy of the password, with guessable parts charged what it takes to guess them
+    pub fn estimate(password: &str) -> Self {
+        let chars: Vec<char> = password.chars().collect();
+        let per_char = pool(&chars).log2();
+        let parts = parts(&chars, per_char);
+        // cheapest cover of the first i characters, and the part ending it if it was one
+        let mut best = vec![(0.0, None); chars.len() + 1];
+        for end in 1..=chars.len() {
+            best[end] = (best[end - 1].0 + per_char, None);
+            for (index, part) in parts.iter().enumerate() {
+                let bits = best[part.start].0 + part.bits;
+                if part.end == end && bits < best[end].0 {
+                    best[end] = (bits, Some(index));
+                }
+            }
+        }
+        let mut pattern: Option<(usize, Pattern)> = None;
+        let mut end = chars.len();
+        while end > 0 {
+            match best[end].1 {
+                Some(index) => {
+                    let part = &parts[index];
+                    let length = part.end - part.start;
+                    if pattern.map_or(true, |(longest, _)| length > longest) {
+                        pattern = Some((length, part.pattern));
+                    }
+                    end = part.start;
+                }
+                None => end -= 1,
+            }
+        }
+        Self {
+            bits: best[chars.len()].0,
+            pattern: pattern.map(|(_, pattern)| pattern),
+        }
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.bits < WEAK_BITS
+    }
+
+    pub fn rating(&self) -> Rating {
+        match self.bits {
+            bits if bits < 28.0 => Rating::VeryWeak,
+            bits if bits < WEAK_BITS => Rating::Weak,
+            bits if bits < 70.0 => Rating::Fair,
+            bits if bits < 100.0 => Rating::Strong,
+            _ => Rating::VeryStrong,
+        }
+    }
+}
+
+impl Display for Strength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.0} bits", self.bits)
+    }
+}
+
+/// how strong manually entered passwords of a vault have to be
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StrengthPolicy {
+    /// estimated bits below which a password is too weak
+    pub min_bits: f64,
+    /// refuse passwords below the minimum instead of asking whether to keep them
+    pub enforce: bool,
+}
+
+impl Default for StrengthPolicy {
+    fn default() -> Self {
+        Self {
+            min_bits: WEAK_BITS,
+            enforce: false,
+        }
+    }
+}
+
+impl StrengthPolicy {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn accepts(&self, strength: &Strength) -> bool {
+        strength.bits >= self.min_bits
+    }
+}
+
+/// number of characters in the character classes the password uses
+fn pool(chars: &[char]) -> f64 {
+    let mut pool = 0u32;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') {
+        pool += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+    f64::from(pool.max(1))
+}
+
+/// every guessable part of the password
+fn parts(chars: &[char], per_char: f64) -> Vec<Part> {
+    let lower: Vec<char> = chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+    let unleet: Vec<char> = lower.iter().map(|c| unleet(*c)).collect();
+    let mut parts = vec![];
+    for (rank, word) in COMMON.iter().enumerate() {
+        let word: Vec<char> = word.chars().collect();
+        for start in 0..chars.len().saturating_sub(word.len() - 1) {
+            let end = start + word.len();
+            let plain = lower[start..end] == word[..];
+            if !plain && unleet[start..end] != word[..] {
+                continue;
+            }
+            // a bit each for capitals and substitutions being used at all
+            let mut bits = ((rank + 2) as f64).log2();
+            if chars[start..end].iter().any(|c| c.is_ascii_uppercase()) {
+                bits += 1.0;
+            }
+            if !plain {
+                bits += 1.0;
+            }
+            parts.push(Part {
+                start,
+                end,
+                bits,
+                pattern: Pattern::Common,
+            });
+        }
+    }
+    for (start, end) in runs(&lower, |a, b| a == b) {
+        parts.push(Part {
+            start,
+            end,
+            bits: per_char + ((end - start) as f64).log2(),
+            pattern: Pattern::Repeat,
+        });
+    }
+    for step in [1, -1] {
+        let follows = |a: char, b: char| {
+            a.is_ascii_alphanumeric()
+                && b as i64 - a as i64 == step
+                && a.is_ascii_digit() == b.is_ascii_digit()
+        };
+        for (start, end) in runs(&lower, follows) {
+            parts.push(Part {
+                start,
+                end,
+                bits: per_char + ((end - start) as f64).log2() + f64::from(u8::from(step < 0)),
+                pattern: Pattern::Sequence,
+            });
+        }
+    }
+    for row in KEYBOARD_ROWS {
+        let row: Vec<char> = row.chars().collect();
+        let next = |a: char, b: char| {
+            row.windows(2)
+                .any(|keys| keys[0] == a && keys[1] == b || keys[0] == b && keys[1] == a)
+        };
+        for (start, end) in runs(&lower, next) {
+            if end - start >= 4 {
+                parts.push(Part {
+                    start,
+                    end,
+                    bits: 6.0 + ((end - start) as f64).log2(),
+                    pattern: Pattern::Keyboard,
+                });
+            }
+        }
+    }
+    for start in 0..chars.len().saturating_sub(3) {
+        let year: String = chars[start..start + 4].iter().collect();
+        if let Ok(year) = year.parse::<u32>() {
+            if (1900..2100).contains(&year) {
+                parts.push(Part {
+                    start,
+                    end: start + 4,
+                    bits: 200f64.log2(),
+                    pattern: Pattern::Year,
+                });
+            }
+        }
+    }
+    parts
+}
+
+/// the longest stretches of at least three characters where each follows the one before
+fn runs(chars: &[char], follows: impl Fn(char, char) -> bool) -> Vec<(usize, usize)> {
+    let mut runs = vec![];
+    let mut start = 0;
+    for end in 1..=chars.len() {
+        if end == chars.len() || !follows(chars[end - 1], chars[end]) {
+            if end - start >= 3 {
+                runs.push((start, end));
+            }
+            start = end;
+        }
+    }
+    runs
+}
+
+/// the letter a common substitution stands for
+fn unleet(c: char) -> char {
+    match c {
+        '4' | '@' => 'a',
+        '8' => 'b',
+        '(' => 'c',
+        '3' => 'e',
+        '6' | '9' => 'g',
+        '1' | '!' | '|' => 'i',
+        '0' => 'o',
+        '5' | '$' => 's',
+        '7' | '+' => 't',
+        '2' => 'z',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pattern, Rating, Strength, StrengthPolicy};
+
+    fn pattern(password: &str) -> Option<Pattern> {
+        Strength::estimate(password).pattern
+    }
+
+    #[test]
+    fn common_passwords() {
+        for password in ["password", "P@ssw0rd", "Password1", "letmein"] {
+            let strength = Strength::estimate(password);
+            assert_eq!(strength.pattern, Some(Pattern::Common), "{password}");
+            assert_eq!(strength.rating(), Rating::VeryWeak, "{password}");
+        }
+    }
+
+    #[test]
+    fn patterns() {
+        assert_eq!(pattern("aaaaaaaaaaaa"), Some(Pattern::Repeat));
+        assert_eq!(pattern("lmnopqrs"), Some(Pattern::Sequence));
+        assert_eq!(pattern("98765"), Some(Pattern::Sequence));
+        assert_eq!(pattern("dfghjkl"), Some(Pattern::Keyboard));
+        assert_eq!(pattern("1987"), Some(Pattern::Year));
+        assert!(Strength::estimate("aaaaaaaaaaaa").is_weak());
+    }
+
+    #[test]
+    fn random_passwords() {
+        let strength = Strength::estimate("x7$Kq!m2Vz#9Lp");
+        assert_eq!(strength.pattern, None);
+        assert!((strength.bits - 14.0 * 95f64.log2()).abs() < 1e-9);
+        assert_eq!(strength.rating(), Rating::Strong);
+    }
+
+    #[test]
+    fn patterns_cost_less_than_their_characters() {
+        let plain = Strength::estimate("qzvhwtkbnr");
+        let keyboard = Strength::estimate("qwertasdfg");
+        assert!(keyboard.bits < plain.bits);
+        let year = Strength::estimate("qzvhwtkbnr1987");
+        let digits = Strength::estimate("qzvhwtkbnr3851");
+        assert!(year.bits < digits.bits);
+    }
+
+    #[test]
+    fn empty() {
+        let strength = Strength::estimate("");
+        assert_eq!(strength.bits, 0.0);
+        assert_eq!(strength.pattern, None);
+    }
+
+    #[test]
+    fn policy() {
+        let policy = StrengthPolicy::default();
+        assert!(policy.accepts(&Strength::estimate("x7$Kq!m2Vz#9Lp")));
+        assert!(!policy.accepts(&Strength::estimate("password")));
+    }
+}
@@ -0,0 +1,127 @@
+//! named layouts for new entries, configured in `pants/templates.toml`
+
+use serde::{Deserialize, Serialize};
+
+use crate::store::{FieldKind, StoreHash};
+
+/// a field that entries made from a [Template] start with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateField {
+    pub name: String,
+    #[serde(default)]
+    pub kind: FieldKind,
+    /// fill the value from a password spec instead of asking for it
+    #[serde(default)]
+    pub generate: bool,
+    /// password spec for generated values, the client's configured spec is used when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec: Option<String>,
+}
+
+impl TemplateField {
+    pub fn new(name: impl Into<String>, kind: FieldKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            generate: false,
+            spec: None,
+        }
+    }
+
+    pub fn generated(mut self) -> Self {
+        self.generate = true;
+        self
+    }
+
+    pub fn spec(mut self, spec: impl Into<String>) -> Self {
+        self.generate = true;
+        self.spec = Some(spec.into());
+        self
+    }
+}
+
+/// the fields, in order, that an entry made from the template has
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Template {
+    pub fields: Vec<TemplateField>,
+}
+
+impl Template {
+    pub fn new(fields: impl Into<Vec<TemplateField>>) -> Self {
+        Self {
+            fields: fields.into(),
+        }
+    }
+
+    /// [StoreHash] with every field of the template, values come from `value` which gets
+    /// the field and returns [None] to leave it empty
+    pub fn convert_default(
+        &self,
+        mut value: impl FnMut(&TemplateField) -> Option<crate::Password>,
+    ) -> StoreHash {
+        let mut hash = StoreHash::default();
+        for field in &self.fields {
+            let value = value(field).unwrap_or_else(|| String::new().into());
+            hash.insert_kind(field.name.clone(), field.kind, value);
+        }
+        hash
+    }
+
+    /// templates available without any configuration
+    pub fn defaults() -> Vec<(String, Template)> {
+        use FieldKind::*;
+        vec![
+            (
+                "login".into(),
+                Template::new([
+                    TemplateField::new("Username", Username),
+                    TemplateField::new("Password", Password).generated(),
+                    TemplateField::new("URL", Url),
+                ]),
+            ),
+            (
+                "credit-card".into(),
+                Template::new([
+                    TemplateField::new("Cardholder", Username),
+                    TemplateField::new("Number", Generic),
+                    TemplateField::new("Expiry", Date),
+                    TemplateField::new("CVV", Generic),
+                    TemplateField::new("PIN", Password),
+                ]),
+            ),
+            (
+                "ssh-key".into(),
+                Template::new([
+                    TemplateField::new("Private key", Generic),
+                    TemplateField::new("Public key", Note),
+                    TemplateField::new("Passphrase", Password).generated(),
+                ]),
+            ),
+            (
+                "api-token".into(),
+                Template::new([
+                    TemplateField::new("Token", Password),
+                    TemplateField::new("URL", Url),
+                ]),
+            ),
+            (
+                "wifi".into(),
+                Template::new([
+                    TemplateField::new("SSID", Note),
+                    // symbols are a pain to type on phones and TVs
+                    TemplateField::new("Password", Password)
+                        .spec("24//1+|:upper://1+|:lower://1+|:number:"),
+                ]),
+            ),
+            (
+                "server".into(),
+                Template::new([
+                    TemplateField::new("Host", Note),
+                    TemplateField::new("Port", Number),
+                    TemplateField::new("Username", Username),
+                    TemplateField::new("Password", Password).generated(),
+                ]),
+            ),
+        ]
+    }
+}
@@ -0,0 +1,350 @@
+//! time based one-time passwords ([RFC 6238](https://www.rfc-editor.org/rfc/rfc6238)) generated
+//! from seeds stored in [FieldKind::Totp](crate::store::FieldKind::Totp) fields
+
+use std::{
+    fmt::Display,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{
+    digest::{core_api::BlockSizeUser, Digest, KeyInit},
+    Mac, SimpleHmac,
+};
+use secrecy::{ExposeSecret, SecretVec};
+
+use crate::errors::TotpError;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl FromStr for Algorithm {
+    type Err = TotpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "SHA1" => Ok(Self::Sha1),
+            "SHA256" => Ok(Self::Sha256),
+            "SHA512" => Ok(Self::Sha512),
+            _ => Err(TotpError::Algorithm(s.to_string())),
+        }
+    }
+}
+
+/// generator for the codes of a single seed
+pub struct Totp {
+    secret: SecretVec<u8>,
+    pub algorithm: Algorithm,
+    pub digits: u32,
+    /// seconds each code is valid for
+    pub period: u64,
+    pub issuer: Option<String>,
+    pub account: Option<String>,
+}
+
+/// a code and how long it remains valid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Code {
+    pub code: String,
+    /// seconds until the next code
+    pub remaining: u64,
+}
+
+impl Display for Code {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}s left)", self.code, self.remaining)
+    }
+}
+
+impl Totp {
+    /// generator with the usual parameters, 6 digits every 30 seconds using SHA1
+    pub fn new(secret: Vec<u8>) -> Self {
+        Self {
+            secret: secret.into(),
+            algorithm: Algorithm::default(),
+            digits: 6,
+            period: 30,
+            issuer: None,
+            account: None,
+        }
+    }
+
+    pub fn code(&self) -> Code {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.code_at(now)
+    }
+
+    /// code for a unix timestamp in seconds
+    pub fn code_at(&self, time: u64) -> Code {
+        let counter = (time / self.period).to_be_bytes();
+        let hash = match self.algorithm {
+            Algorithm::Sha1 => hmac::<sha1::Sha1>(self.secret.expose_secret(), &counter),
+            Algorithm::Sha256 => hmac::<sha2::Sha256>(self.secret.expose_secret(), &counter),
+            Algorithm::Sha512 => hmac::<sha2::Sha512>(self.secret.expose_secret(), &counter),
+        };
+        // dynamic truncation from RFC 4226
+        let offset = (hash[hash.len() - 1] & 0xf) as usize;
+        let binary = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+        let code = binary as u64 % 10u64.pow(self.digits);
+        Code {
+            code: format!("{code:0width$}", width = self.digits as usize),
+            remaining: self.period - time % self.period,
+        }
+    }
+}
+
+fn hmac<D: Digest + BlockSizeUser>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac =
+        <SimpleHmac<D> as KeyInit>::new_from_slice(key).expect("hmac accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+impl FromStr for Totp {
+    type Err = TotpError;
+
+    /// either a base32 encoded seed or an `otpauth://totp/` URI
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.strip_prefix("otpauth://") {
+            Some(uri) => parse_uri(uri),
+            None => Ok(Self::new(decode_base32(s)?)),
+        }
+    }
+}
+
+fn parse_uri(uri: &str) -> Result<Totp, TotpError> {
+    let rest = uri
+        .strip_prefix("totp/")
+        .ok_or(TotpError::Uri("only totp URIs are supported"))?;
+    let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let label = percent_decode(label);
+    let (label_issuer, account) = match label.split_once(':') {
+        Some((issuer, account)) => (Some(issuer.trim().to_string()), account.trim().to_string()),
+        None => (None, label.trim().to_string()),
+    };
+
+    let mut secret = None;
+    let mut totp = Totp::new(vec![]);
+    totp.issuer = label_issuer;
+    totp.account = (!account.is_empty()).then_some(account);
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value);
+        match key.to_lowercase().as_str() {
+            "secret" => secret = Some(decode_base32(&value)?),
+            "algorithm" => totp.algorithm = value.parse()?,
+            "digits" => {
+                totp.digits = value
+                    .parse()
+                    .ok()
+                    .filter(|d| (6..=10).contains(d))
+                    .ok_or(TotpError::Uri("digits must be between 6 and 10"))?
+            }
+            "period" => {
+                totp.period = value
+                    .parse()
+                    .ok()
+                    .filter(|p| *p > 0)
+                    .ok_or(TotpError::Uri("period must be a positive number"))?
+            }
+            "issuer" => totp.issuer = Some(value),
+            _ => {}
+        }
+    }
+    totp.secret = secret.ok_or(TotpError::Uri("missing secret"))?.into();
+    Ok(totp)
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// decode RFC 4648 base32, ignoring case, whitespace, dashes and padding
+pub fn decode_base32(s: &str) -> Result<Vec<u8>, TotpError> {
+    let mut bits: u64 = 0;
+    let mut count = 0;
+    let mut decoded = vec![];
+    for c in s.chars().filter(|c| !c.is_whitespace() && *c != '-') {
+        if c == '=' {
+            break;
+        }
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            _ => return Err(TotpError::Secret),
+        };
+        bits = (bits << 5) | value;
+        count += 5;
+        if count >= 8 {
+            count -= 8;
+            decoded.push((bits >> count) as u8);
+            bits &= (1 << count) - 1;
+        }
+    }
+    if decoded.is_empty() {
+        return Err(TotpError::Secret);
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret;
+
+    use super::{decode_base32, Algorithm, Totp};
+
+    /// times of the RFC 6238 appendix B test vectors
+    const TIMES: [u64; 6] = [
+        59,
+        1111111109,
+        1111111111,
+        1234567890,
+        2000000000,
+        20000000000,
+    ];
+
+    /// generator with the RFC 6238 test seed for the algorithm, repeated to the hash's length
+    fn rfc_6238(algorithm: Algorithm) -> Totp {
+        let length = match algorithm {
+            Algorithm::Sha1 => 20,
+            Algorithm::Sha256 => 32,
+            Algorithm::Sha512 => 64,
+        };
+        let seed = b"1234567890".iter().cycle().take(length).copied().collect();
+        let mut totp = Totp::new(seed);
+        totp.algorithm = algorithm;
+        totp.digits = 8;
+        totp
+    }
+
+    fn codes(totp: &Totp) -> Vec<String> {
+        TIMES.iter().map(|time| totp.code_at(*time).code).collect()
+    }
+
+    #[test]
+    fn rfc_6238_sha1() {
+        assert_eq!(
+            codes(&rfc_6238(Algorithm::Sha1)),
+            ["94287082", "07081804", "14050471", "89005924", "69279037", "65353130"]
+        );
+    }
+
+    #[test]
+    fn rfc_6238_sha256() {
+        assert_eq!(
+            codes(&rfc_6238(Algorithm::Sha256)),
+            ["46119246", "68084774", "67062674", "91819424", "90698825", "77737706"]
+        );
+    }
+
+    #[test]
+    fn rfc_6238_sha512() {
+        assert_eq!(
+            codes(&rfc_6238(Algorithm::Sha512)),
+            ["90693936", "25091201", "99943326", "93441116", "38618901", "47863826"]
+        );
+    }
+
+    #[test]
+    fn remaining() {
+        let totp = Totp::new(b"12345678901234567890".to_vec());
+        assert_eq!(totp.code_at(59).remaining, 1);
+        assert_eq!(totp.code_at(60).remaining, 30);
+        assert_eq!(totp.code_at(59).code.len(), 6);
+    }
+
+    #[test]
+    fn rfc_4648_base32() {
+        for (encoded, decoded) in [
+            ("MY======", "f"),
+            ("MZXQ====", "fo"),
+            ("MZXW6===", "foo"),
+            ("MZXW6YQ=", "foob"),
+            ("MZXW6YTB", "fooba"),
+            ("MZXW6YTBOI======", "foobar"),
+        ] {
+            assert_eq!(decode_base32(encoded).unwrap(), decoded.as_bytes());
+        }
+    }
+
+    #[test]
+    fn base32_leniency() {
+        assert_eq!(decode_base32("mzxw 6ytb-oi").unwrap(), b"foobar");
+        assert!(decode_base32("").is_err());
+        assert!(decode_base32("MZXW1").is_err());
+    }
+
+    #[test]
+    fn otpauth_uri() {
+        let totp: Totp = "otpauth://totp/ACME%20Co:john@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACME+Corp&algorithm=SHA256&digits=8&period=60"
+            .parse()
+            .unwrap();
+        assert_eq!(totp.secret.expose_secret(), b"12345678901234567890");
+        assert_eq!(totp.algorithm, Algorithm::Sha256);
+        assert_eq!(totp.digits, 8);
+        assert_eq!(totp.period, 60);
+        assert_eq!(totp.issuer.as_deref(), Some("ACME Corp"));
+        assert_eq!(totp.account.as_deref(), Some("john@example.com"));
+    }
+
+    #[test]
+    fn otpauth_uri_defaults() {
+        let totp: Totp = "otpauth://totp/john?secret=MZXW6YTBOI".parse().unwrap();
+        assert_eq!(totp.secret.expose_secret(), b"foobar");
+        assert_eq!(totp.algorithm, Algorithm::Sha1);
+        assert_eq!((totp.digits, totp.period), (6, 30));
+        assert_eq!(totp.issuer, None);
+        assert_eq!(totp.account.as_deref(), Some("john"));
+    }
+
+    #[test]
+    fn invalid_otpauth_uris() {
+        for uri in [
+            "otpauth://hotp/john?secret=MZXW6YTBOI&counter=0",
+            "otpauth://totp/john",
+            "otpauth://totp/john?secret=MZXW6YTBOI&digits=5",
+            "otpauth://totp/john?secret=MZXW6YTBOI&period=0",
+            "otpauth://totp/john?secret=MZXW6YTBOI&algorithm=MD5",
+            "otpauth://totp/john?secret=not-base32!",
+        ] {
+            assert!(uri.parse::<Totp>().is_err(), "{uri}");
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! finding the entries stored for a site from their URL fields, like a browser extension would
+//!
+//! Only the public values of the URL fields are used, so lookups don't need the vault password
+//! unless the vault keeps them private. Registrable domains are worked out with the bundled public
+//! suffix list, hosts under a suffix it doesn't know are only matched by host and parent domain.
+
+use std::{fmt::Display, str::FromStr, sync::OnceLock};
+
+use publicsuffix::{List, Psl};
+use serde::Serialize;
+
+use crate::errors::ClientError;
+
+/// the public suffix list from <https://publicsuffix.org>, under the Mozilla Public License 2.0
+const PUBLIC_SUFFIXES: &str = include_str!("public_suffix_list.dat");
+
+/// the parsed public suffix list, parsed on first use
+fn public_suffixes() -> &'static List {
+    static LIST: OnceLock<List> = OnceLock::new();
+    LIST.get_or_init(|| {
+        PUBLIC_SUFFIXES
+            .parse()
+            .expect("the bundled public suffix list parses")
+    })
+}
+
+/// the parts of a URL that decide which site it belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Site {
+    /// [None] when the URL was given without one, it then matches any scheme
+    pub scheme: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl FromStr for Site {
+    type Err = ClientError;
+
+    /// accepts full URLs as well as bare hosts like `accounts.google.com`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ClientError::InvalidUrl(s.into());
+        let s = s.trim();
+        let (scheme, rest) = match s.split_once("://") {
+            Some((scheme, rest)) => (Some(scheme.to_lowercase()), rest),
+            None => (None, s),
+        };
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        let authority = authority.rsplit('@').next().unwrap_or_default();
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => {
+                (host, Some(port.parse().map_err(|_| invalid())?))
+            }
+            _ => (authority, None),
+        };
+        let host = host.trim_end_matches('.').to_lowercase();
+        if host.is_empty() || host.contains(char::is_whitespace) {
+            return Err(invalid());
+        }
+        Ok(Self { scheme, host, port })
+    }
+}
+
+impl Display for Site {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(scheme) = &self.scheme {
+            write!(f, "{scheme}://")?;
+        }
+        write!(f, "{}", self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{port}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Site {
+    /// the port, filled in from the scheme when it's left out
+    fn effective_port(&self) -> Option<u16> {
+        self.port.or(match self.scheme.as_deref() {
+            Some("http") => Some(80),
+            Some("https") => Some(443),
+            _ => None,
+        })
+    }
+
+    fn is_ip(&self) -> bool {
+        self.host.starts_with('[') || self.host.parse::<std::net::Ipv4Addr>().is_ok()
+    }
+
+    /// whether the whole host is a public suffix, like `github.io`, so hosts under it belong to
+    /// different sites
+    fn is_public_suffix(&self) -> bool {
+        public_suffixes()
+            .suffix(self.host.as_bytes())
+            .is_some_and(|suffix| suffix.is_known() && suffix.as_bytes() == self.host.as_bytes())
+    }
+
+    /// the public suffix and the label in front of it, like `example.co.uk` for
+    /// `www.example.co.uk`, [None] for IP addresses and hosts not under a known public suffix
+    pub fn registrable_domain(&self) -> Option<&str> {
+        if self.is_ip() {
+            return None;
+        }
+        let list = public_suffixes();
+        if !list.suffix(self.host.as_bytes())?.is_known() {
+            return None;
+        }
+        let domain = list.domain(self.host.as_bytes())?;
+        let start = self.host.len() - domain.as_bytes().len();
+        Some(&self.host[start..])
+    }
+
+    /// how a stored URL's site covers this one, [None] when it's another site
+    pub fn matched_by(&self, stored: &Site) -> Option<MatchKind> {
+        if self.host == stored.host {
+            let scheme = self.scheme.is_none() || self.scheme == stored.scheme;
+            let port = self.port.is_none() || self.effective_port() == stored.effective_port();
+            if scheme && port {
+                return Some(MatchKind::Origin);
+            }
+            return Some(MatchKind::Host);
+        }
+        if self.is_ip() || stored.is_ip() {
+            return None;
+        }
+        if self.host.ends_with(&format!(".{}", stored.host)) && !stored.is_public_suffix() {
+            return Some(MatchKind::Subdomain);
+        }
+        match (self.registrable_domain(), stored.registrable_domain()) {
+            (Some(domain), Some(stored)) if domain == stored => Some(MatchKind::Domain),
+            _ => None,
+        }
+    }
+}
+
+/// how closely a stored URL matches the one looked up, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// same scheme, host and port
+    Origin,
+    /// same host but another scheme or port
+    Host,
+    /// the stored URL is for a parent domain of the one looked up
+    Subdomain,
+    /// another host of the same registrable domain
+    Domain,
+}
+
+impl Display for MatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Origin => write!(f, "same origin"),
+            Self::Host => write!(f, "same host"),
+            Self::Subdomain => write!(f, "parent domain"),
+            Self::Domain => write!(f, "same domain"),
+        }
+    }
+}
+
+/// a URL field of an entry matching the URL looked up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UrlMatch {
+    pub vault: String,
+    pub key: String,
+    pub field: String,
+    pub url: String,
+    pub kind: MatchKind,
+}
+
+impl Display for UrlMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}): {} {}, {}",
+            self.key, self.vault, self.field, self.url, self.kind
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchKind, Site};
+
+    fn matched(query: &str, stored: &str) -> Option<MatchKind> {
+        let query: Site = query.parse().unwrap();
+        query.matched_by(&stored.parse().unwrap())
+    }
+
+    #[test]
+    fn origin() {
+        assert_eq!(
+            matched("https://example.com/login", "https://example.com"),
+            Some(MatchKind::Origin)
+        );
+        assert_eq!(
+            matched("example.com", "https://example.com:8443"),
+            Some(MatchKind::Origin)
+        );
+        assert_eq!(
+            matched("https://example.com:443", "https://example.com"),
+            Some(MatchKind::Origin)
+        );
+    }
+
+    #[test]
+    fn host() {
+        assert_eq!(
+            matched("http://example.com", "https://example.com"),
+            Some(MatchKind::Host)
+        );
+        assert_eq!(
+            matched("https://example.com:8443", "https://example.com"),
+            Some(MatchKind::Host)
+        );
+    }
+
+    #[test]
+    fn subdomain() {
+        assert_eq!(
+            matched("https://accounts.example.com", "example.com"),
+            Some(MatchKind::Subdomain)
+        );
+        assert_eq!(
+            matched("www.example.co.uk", "example.co.uk"),
+            Some(MatchKind::Subdomain)
+        );
+    }
+
+    #[test]
+    fn domain() {
+        assert_eq!(
+            matched("mail.example.com", "www.example.com"),
+            Some(MatchKind::Domain)
+        );
+        assert_eq!(
+            matched("shop.example.co.uk", "www.example.co.uk"),
+            Some(MatchKind::Domain)
+        );
+    }
+
+    #[test]
+    fn registrable_domain() {
+        let site: Site = "www.example.co.uk".parse().unwrap();
+        assert_eq!(site.registrable_domain(), Some("example.co.uk"));
+        let site: Site = "bucket.s3.amazonaws.com".parse().unwrap();
+        assert_eq!(site.registrable_domain(), Some("bucket.s3.amazonaws.com"));
+        let site: Site = "192.168.1.1".parse().unwrap();
+        assert_eq!(site.registrable_domain(), None);
+        let site: Site = "router.invalidsuffix".parse().unwrap();
+        assert_eq!(site.registrable_domain(), None);
+    }
+
+    #[test]
+    fn different_sites_under_shared_suffixes() {
+        for (query, stored) in [
+            ("shop.co.th", "bank.co.th"),
+            ("a.s3.amazonaws.com", "b.s3.amazonaws.com"),
+            ("a.azurewebsites.net", "b.azurewebsites.net"),
+            ("a.firebaseapp.com", "b.firebaseapp.com"),
+            ("tax.gov.in", "police.gov.in"),
+            ("pku.edu.cn", "tsinghua.edu.cn"),
+            ("alice.github.io", "bob.github.io"),
+            ("alice.github.io", "github.io"),
+            ("example.co.uk", "co.uk"),
+        ] {
+            assert_eq!(matched(query, stored), None, "{query} against {stored}");
+        }
+    }
+
+    #[test]
+    fn unrelated() {
+        assert_eq!(matched("example.com", "example.org"), None);
+        assert_eq!(matched("notexample.com", "example.com"), None);
+        assert_eq!(matched("10.0.0.1", "10.0.0.2"), None);
+        assert_eq!(matched("a.localhost", "b.localhost"), None);
+    }
+}
@@ -1,4 +1,8 @@
-use std::path::PathBuf;
+use std::{
+    fs::{self, DirBuilder, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use chrono::{DateTime, Local, NaiveDateTime, ParseError};
 
@@ -25,3 +29,127 @@ pub fn base_path() -> PathBuf {
         };
     base_dir
 }
+
+/// create a directory and its parents, restricted to the owner
+pub fn create_dir_all(path: &Path) -> std::io::Result<()> {
+    let mut builder = DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(path)
+}
+
+/// create or truncate a file, restricted to the owner whether it existed or not
+pub fn create_file(path: &Path) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(path)?;
+    // the mode only applies to new files, older ones may still be readable by others
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(file)
+}
+
+/// whether the file is writable by the group or others
+#[cfg(unix)]
+pub fn shared_writable(path: &Path) -> std::io::Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+
+    Ok(path.metadata()?.permissions().mode() & 0o022 != 0)
+}
+
+#[cfg(not(unix))]
+pub fn shared_writable(_path: &Path) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+/// whether the file belongs to a different user than the one running pants
+#[cfg(unix)]
+pub fn foreign_owner(path: &Path) -> std::io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    // SAFETY: geteuid has no preconditions and cannot fail
+    let uid = unsafe { libc::geteuid() };
+    Ok(path.metadata()?.uid() != uid)
+}
+
+#[cfg(not(unix))]
+pub fn foreign_owner(_path: &Path) -> std::io::Result<bool> {
+    Ok(false)
+}
+
+/// best effort at removing a file's contents from disk, the file is overwritten with zeros,
+/// truncated and synced before being unlinked
+pub fn erase_file(path: &Path) -> std::io::Result<()> {
+    let mut remaining = fs::metadata(path)?.len();
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let zeros = [0u8; 4096];
+    while remaining > 0 {
+        let n = remaining.min(zeros.len() as u64);
+        file.write_all(&zeros[..n as usize])?;
+        remaining -= n;
+    }
+    file.sync_all()?;
+    file.set_len(0)?;
+    file.sync_all()?;
+    drop(file);
+    fs::remove_file(path)
+}
+
+/// erase every file in the directory and then remove the directory
+pub fn erase_dir(path: &Path) -> std::io::Result<()> {
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            erase_dir(&entry.path())?;
+        } else if file_type.is_file() {
+            erase_file(&entry.path())?;
+        }
+    }
+    fs::remove_dir_all(path)
+}
+
+/// copy the files of a directory and its subdirectories, the directories are created owner only
+pub fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else if file_type.is_file() {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    #[test]
+    fn existing_files_are_restricted() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        super::create_file(&path).unwrap();
+        let mode = path.metadata().unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
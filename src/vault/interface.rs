@@ -1,22 +1,32 @@
 use core::panic;
-use std::{cell::RefCell, path::PathBuf, rc::Rc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError, Weak},
+    thread,
+    time::{Duration, Instant},
+};
 
 use aes_gcm::{Aes256Gcm, Key};
 use argon2::password_hash::SaltString;
 use rand::rngs::OsRng;
+use secrecy::Zeroize;
 
 use crate::{
+    attachment::{Attachment, AttachmentIndex, AttachmentInfo, AttachmentLimits, Export},
+    audit::Auditor,
     command::{Command, Commands},
     config::vault_config::VaultConfig,
-    errors::{ClientError, ManagerError},
+    errors::{AttachmentError, ClientError, CommunicationError, ManagerError},
     file::{BackupFile, ProjectFile, RecordFile, SaveDir, SchemaFile, VaultFile},
-    message::Message,
+    message::{BatchStep, Message},
     output::Output,
     reads::Reads,
+    rotation::{self, Rotated, Rotation},
     schema::Schema,
     secure::{Encrypted, SecureData},
-    store::Store,
-    Password,
+    store::{Changes, Store},
+    utils, Password,
 };
 
 use super::{
@@ -40,6 +50,9 @@ impl VaultInterface {
 
         Self { config }
     }
+    pub fn with_config(config: VaultConfig) -> Self {
+        Self { config }
+    }
     pub fn delete(&self, password: Password) -> anyhow::Result<()> {
         // ensure password is right
         VaultHandler::get_interface(password, self.config.save_dir())?;
@@ -48,7 +61,7 @@ impl VaultInterface {
         Ok(())
     }
     pub fn delete_empty(&self) -> anyhow::Result<()> {
-        if self.is_empty() {
+        if self.is_empty()? {
             let dir = self.config.save_dir();
             let _ = dir.remove();
             Ok(())
@@ -58,23 +71,232 @@ impl VaultInterface {
     }
     pub fn receive(&self, message: Message) -> anyhow::Result<Output> {
         match message {
-            Message::Schema => Ok(self.get_schema().into()),
+            Message::Schema => Ok(self.with_policy(self.get_schema()?).into()),
+            message @ Message::Metadata(_) => {
+                match VaultHandler::receive(message, self.config.save_dir(), self.config.limits())?
+                {
+                    Output::Schema(schema) => Ok(self.with_policy(schema).into()),
+                    output => Ok(output),
+                }
+            }
             Message::BackupList => Ok(self.config.save_dir().backup_file_all().into()),
-            _ => VaultHandler::receive(message, self.config.save_dir()),
+            Message::PruneBackups(keep) => Ok(self.prune_backups(keep)?.into()),
+            _ => VaultHandler::receive(message, self.config.save_dir(), self.config.limits()),
+        }
+    }
+
+    /// decrypt the vault once and keep the key for the operations of the session, it locks after
+    /// going unused for `timeout`
+    pub fn unlock(&self, password: Password, timeout: Duration) -> anyhow::Result<VaultSession> {
+        let handler = VaultHandler::load_interface(password, self.config.save_dir())?;
+        Ok(VaultSession::new(handler, timeout))
+    }
+
+    /// add the vault's passwords to the audit
+    pub fn audit(
+        &self,
+        password: Password,
+        name: &str,
+        auditor: &mut Auditor,
+    ) -> anyhow::Result<()> {
+        let handler = VaultHandler::load_interface(password, self.config.save_dir())?;
+        auditor.check(name, &handler.vault, self.config.policy())
+    }
+
+    /// move or copy `keys` from this vault into `other`, backing up both vaults first
+    pub fn transfer(
+        &self,
+        password: Password,
+        other: &VaultInterface,
+        other_password: Password,
+        keys: Vec<String>,
+        keep: bool,
+    ) -> anyhow::Result<Vec<BackupFile>> {
+        let mut source = VaultHandler::load_interface(password, self.config.save_dir())?;
+        let mut destination =
+            VaultHandler::load_interface(other_password, other.config.save_dir())?;
+        source.transfer(&mut destination, other.config.limits(), keys, keep)
+    }
+
+    /// apply every leftover record file in the order they were written
+    pub fn replay(&self, password: Password) -> anyhow::Result<()> {
+        let mut interface = VaultHandler::get_interface(password, self.config.save_dir())?;
+        let mut records = self.config.save_dir().record_file_all();
+        records.sort_by_key(|record| record.timestamp());
+        for record in records {
+            interface.apply_unfinished(record)?;
+        }
+        Ok(())
+    }
+
+    fn prune_backups(&self, keep: usize) -> anyhow::Result<Vec<BackupFile>> {
+        let save_dir = self.config.save_dir();
+        let mut backups = save_dir.backup_file_all();
+        backups.sort_by_key(|backup| std::cmp::Reverse(backup.timestamp()));
+        let removed = backups.split_off(keep.min(backups.len()));
+        for backup in &removed {
+            save_dir.delete_file(&backup.path())?;
+            let attachments = save_dir.attachment_backup_dir(backup);
+            if attachments.exists() {
+                save_dir.delete_dir(&attachments)?;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// strip the tags, folders and public values from the schema file, they come back when the
+    /// vault is saved without hiding them
+    pub fn hide_metadata(&self) -> anyhow::Result<()> {
+        let mut schema_file = self.config.save_dir().schema_file();
+        if !schema_file.exists() {
+            return Ok(());
+        }
+        let mut schema = self.get_schema()?;
+        schema.hide_metadata();
+        schema_file.write(&schema)
+    }
+
+    /// strip the values of the public fields from the schema file, they come back when the vault
+    /// is saved while publishing them
+    pub fn unpublish_fields(&self) -> anyhow::Result<()> {
+        let mut schema_file = self.config.save_dir().schema_file();
+        if !schema_file.exists() {
+            return Ok(());
         }
+        let mut schema = self.get_schema()?;
+        schema.unpublish();
+        schema_file.write(&schema)
     }
 
-    fn get_schema(&self) -> Schema {
-        let schema_file: SchemaFile = self.config.save_dir().schema_file();
-        schema_file
+    /// the schema with the vault's age and strength policies attached
+    fn with_policy(&self, mut schema: Schema) -> Schema {
+        schema.policy = self.config.policy().clone();
+        schema.strength = self.config.strength();
+        schema
+    }
+
+    /// the schema file's contents, an empty schema when it's missing or unreadable
+    fn get_schema(&self) -> anyhow::Result<Schema> {
+        let save_dir = self.config.save_dir();
+        let schema_file: SchemaFile = save_dir.schema_file();
+        if !schema_file.exists() {
+            return Ok(Schema::default());
+        }
+        save_dir.check_permissions(&schema_file.path())?;
+        Ok(schema_file
             .read()
             .map(|data| data.deserialize())
-            .unwrap_or(Schema::default())
+            .unwrap_or(Schema::default()))
     }
 
-    fn is_empty(&self) -> bool {
-        let schema = self.get_schema();
-        schema.is_empty()
+    fn is_empty(&self) -> anyhow::Result<bool> {
+        Ok(self.get_schema()?.is_empty())
+    }
+}
+
+/// an unlocked vault, see [VaultInterface::unlock]
+///
+/// The key is kept between operations instead of being derived from the password for each one,
+/// changes saved by other processes are still picked up. Locking or dropping the session zeroes
+/// the key and the decrypted entries, a background thread does the same once the session has gone
+/// unused for its timeout.
+pub struct VaultSession {
+    state: Arc<Mutex<SessionState>>,
+}
+
+struct SessionState {
+    handler: Option<VaultHandler>,
+    timeout: Duration,
+    last_used: Instant,
+}
+
+impl VaultSession {
+    fn new(handler: VaultHandler, timeout: Duration) -> Self {
+        let state = Arc::new(Mutex::new(SessionState {
+            handler: Some(handler),
+            timeout,
+            last_used: Instant::now(),
+        }));
+        let weak = Arc::downgrade(&state);
+        thread::spawn(move || Self::expire(weak, timeout));
+        Self { state }
+    }
+
+    /// lock the session once it goes unused for the timeout, stopping early when the session is
+    /// locked or dropped first
+    fn expire(state: Weak<Mutex<SessionState>>, mut wait: Duration) {
+        loop {
+            thread::sleep(wait);
+            let Some(state) = state.upgrade() else {
+                return;
+            };
+            let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+            if state.handler.is_none() {
+                return;
+            }
+            let elapsed = state.last_used.elapsed();
+            if elapsed >= state.timeout {
+                state.handler = None;
+                return;
+            }
+            wait = state.timeout - elapsed;
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.handler.is_none() || state.last_used.elapsed() > state.timeout
+    }
+
+    /// forget the key and the entries, the vault has to be unlocked again
+    pub fn lock(&mut self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.handler = None;
+    }
+
+    pub fn get(&mut self, key: &str) -> anyhow::Result<Store> {
+        self.with_handler(|handler| handler.read(key))
+    }
+
+    pub fn update(&mut self, key: &str, value: Store) -> anyhow::Result<Reads<Store>> {
+        self.with_handler(|handler| handler.update(key.into(), value))
+    }
+
+    pub fn change(&mut self, key: &str, changes: Changes) -> anyhow::Result<Reads<Store>> {
+        self.with_handler(|handler| handler.change(key.into(), changes))
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> anyhow::Result<()> {
+        self.with_handler(|handler| handler.rename(from.into(), to.into()))
+    }
+
+    pub fn delete(&mut self, key: &str) -> anyhow::Result<()> {
+        self.with_handler(|handler| handler.delete(key.into()))
+    }
+
+    /// run the steps as one transaction, see [Message::Batch]
+    pub fn batch(&mut self, steps: Vec<BatchStep>) -> anyhow::Result<Reads<Store>> {
+        self.with_handler(|handler| handler.batch(steps))
+    }
+
+    /// run the operation on the unlocked vault with what was saved since the last operation,
+    /// locking the session if it timed out
+    fn with_handler<T>(
+        &mut self,
+        operation: impl FnOnce(&mut VaultHandler) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.last_used.elapsed() > state.timeout {
+            state.handler = None;
+        }
+        let handler = state.handler.as_mut().ok_or(ClientError::SessionLocked)?;
+        if let Err(e) = handler.reload() {
+            state.handler = None;
+            return Err(e);
+        }
+        let result = operation(handler);
+        state.last_used = Instant::now();
+        result
     }
 }
 
@@ -85,59 +307,44 @@ pub struct VaultHandler {
     // schema: Schema,
     record: RecordEncrypted,
     save_dir: SaveDir,
-    schema_file: Rc<RefCell<SchemaFile>>,
-    vault_file: Rc<RefCell<VaultFile>>,
-    record_file: Rc<RefCell<RecordFile>>,
+    schema_file: SchemaFile,
+    vault_file: VaultFile,
+    record_file: RecordFile,
 }
 
 impl VaultHandler {
-    pub fn receive(message: Message, save_dir: SaveDir) -> anyhow::Result<Output> {
+    pub fn receive(
+        message: Message,
+        save_dir: SaveDir,
+        limits: AttachmentLimits,
+    ) -> anyhow::Result<Output> {
         match message {
             Message::Get(password, key) => {
-                let command = Command::Read { key };
                 let mut interface = Self::load_interface(password, save_dir)?;
-                let reads = interface.transaction(command.into())?;
-                Ok(reads.into())
+                Ok(interface.get(key)?.into())
             }
             Message::Update(password, key, value) => {
-                let command = Command::Update { key, value };
                 let mut interface = Self::load_interface(password, save_dir)?;
-                let reads = interface.transaction(command.into())?;
-                Ok(reads.into())
+                Ok(interface.update(key, value)?.into())
             }
             Message::Change(password, key, value) => {
-                let command = Command::Read { key: key.clone() };
-                let mut interface = Self::load_interface(password.clone(), save_dir.clone())?;
-                let reads = interface.transaction(command.into())?;
-                let read = reads.data.get(&key).ok_or(ClientError::ReadNothing)?;
-                let to_store = read.update(value);
-
-                let command = Command::Update {
-                    key,
-                    value: to_store,
-                };
-                let reads = interface.transaction(command.into())?;
-                Ok(reads.into())
+                let mut interface = Self::load_interface(password, save_dir)?;
+                Ok(interface.change(key, value)?.into())
             }
             Message::Rename(password, from, to) => {
                 let mut interface = Self::load_interface(password, save_dir)?;
-                let read = Command::Read { key: from.clone() };
-                let data = interface.transaction(read.into())?;
-                let val = data.data.get(&from).ok_or(ClientError::ReadNothing)?;
-                let delete = Command::Delete { key: from };
-                let create = Command::Update {
-                    key: to,
-                    value: val.clone(),
-                };
-                let _ = interface.transaction(vec![delete, create].into())?;
+                interface.rename(from, to)?;
                 Ok(().into())
             }
             Message::Delete(password, key) => {
-                let command = Command::Delete { key };
                 let mut interface = Self::load_interface(password, save_dir)?;
-                let _reads = interface.transaction(command.into())?;
+                interface.delete(key)?;
                 Ok(().into())
             }
+            Message::Batch(password, steps) => {
+                let mut interface = Self::load_interface(password, save_dir)?;
+                Ok(interface.batch(steps)?.into())
+            }
             Message::Backup(password) => {
                 let interface = Self::load_interface(password, save_dir)?;
                 let backup = interface.backup()?;
@@ -148,9 +355,11 @@ impl VaultHandler {
                 let backup = interface.backup()?;
                 let new_vault = VaultEncrypted::new(new_password.clone())?;
                 let key = new_vault.key(new_password);
+                interface.rekey_attachments(key)?;
                 interface.vault_encrypted = new_vault;
                 interface.key = key;
                 interface.save()?;
+                interface.finish_rekey()?;
                 Ok(Output::Backup(backup))
             }
             Message::Restore(password, backup_password, backup_file) => {
@@ -168,23 +377,116 @@ impl VaultHandler {
                 interface.vault_encrypted = backup_vault_enc;
                 interface.key = backup_key;
                 interface.save()?;
+                interface.restore_attachments(&backup_file)?;
                 Ok(Output::Backup(new_backup))
             }
             Message::Export(password) => {
                 let interface = Self::load_interface(password, save_dir)?;
-                let data = interface.vault.export()?;
-                Ok(Output::Content(data))
+                let index = interface.attachment_index()?;
+                let mut attachments = BTreeMap::new();
+                for key in index.data.keys() {
+                    attachments.insert(key.clone(), interface.entry_attachments(&index, key)?);
+                }
+                let export = Export {
+                    entries: interface.vault.data.clone(),
+                    attachments,
+                };
+                Ok(Output::Content(serde_json::to_string(&export)?))
             }
             Message::Import(password, data) => {
                 let mut interface = Self::load_interface(password, save_dir)?;
                 let commands: Vec<Command> = data
+                    .entries
                     .into_iter()
                     .map(|(k, v)| Command::Update { key: k, value: v })
                     .collect();
                 interface.transaction(commands.into())?;
+                for (key, attachments) in data.attachments {
+                    for (name, attachment) in attachments {
+                        interface.attach(&key, &name, attachment, limits)?;
+                    }
+                }
+                Ok(Output::Nothing)
+            }
+            Message::Attach(password, key, name, attachment) => {
+                let interface = Self::load_interface(password, save_dir)?;
+                interface.attach(&key, &name, attachment, limits)?;
+                Ok(Output::Nothing)
+            }
+            Message::GetAttachment(password, key, name) => {
+                let interface = Self::load_interface(password, save_dir)?;
+                let index = interface.attachment_index()?;
+                let info = index
+                    .get(&key, &name)
+                    .ok_or(AttachmentError::NoAttachment(name))?;
+                Ok(Arc::new(interface.read_attachment(info)?).into())
+            }
+            Message::Detach(password, key, name) => {
+                let interface = Self::load_interface(password, save_dir)?;
+                interface.detach(&key, &name)?;
+                Ok(Output::Nothing)
+            }
+            Message::Tag(password, key, add, remove) => {
+                let mut interface = Self::load_interface(password, save_dir)?;
+                let mut store = interface.read(&key)?;
+                store.tags.extend(add);
+                for tag in remove {
+                    store.tags.remove(&tag);
+                }
+                interface.transaction(Command::Update { key, value: store }.into())?;
+                Ok(Output::Nothing)
+            }
+            Message::Folder(password, key, path) => {
+                let mut interface = Self::load_interface(password, save_dir)?;
+                let mut store = interface.read(&key)?;
+                store.set_folder(&path);
+                interface.transaction(Command::Update { key, value: store }.into())?;
                 Ok(Output::Nothing)
             }
-            Message::Schema | Message::BackupList => panic!("Should have been caught by handler"),
+            Message::Metadata(password) => {
+                let interface = Self::load_interface(password, save_dir)?;
+                Ok(interface.vault.schema().into())
+            }
+            Message::Search(password, query, include_secrets) => {
+                let interface = Self::load_interface(password, save_dir)?;
+                Ok(interface.vault.search(&query, include_secrets).into())
+            }
+            Message::RotateEntries(password, filter, field, default) => {
+                let mut interface = Self::load_interface(password, save_dir)?;
+                let mut commands = Commands::new();
+                let mut entries = vec![];
+                for (key, store) in interface.vault.entries() {
+                    if !filter.matches(key, &store.tags) {
+                        continue;
+                    }
+                    let fields = rotation::fields(store, field.as_deref());
+                    if fields.is_empty() {
+                        continue;
+                    }
+                    let value = rotation::regenerate(store, &fields, &default)?;
+                    entries.push(Rotated::new(key, fields, store));
+                    commands.push(Command::Update {
+                        key: key.clone(),
+                        value,
+                    });
+                }
+                if entries.is_empty() {
+                    return Err(ClientError::NoMatches.into());
+                }
+                let backup = interface.backup()?;
+                interface.transaction(commands)?;
+                Ok(Rotation { backup, entries }.into())
+            }
+            Message::ListAttachments(password, key) => {
+                let interface = Self::load_interface(password, save_dir)?;
+                if !interface.vault.data.contains_key(&key) {
+                    return Err(CommunicationError::NoEntry.into());
+                }
+                Ok(interface.attachment_index()?.entry(&key).into())
+            }
+            Message::Schema | Message::BackupList | Message::PruneBackups(_) => {
+                panic!("Should have been caught by handler")
+            }
         }
     }
 
@@ -201,6 +503,7 @@ impl VaultHandler {
         // let schema = Self::get_schema();
         let record = RecordEncrypted::new(password.clone())?;
         let (vault, key, vault_encrypted) = if vault_file.exists() {
+            save_dir.check_permissions(&vault_file.path())?;
             let vault_encrypted = vault_file.read()?.deserialize();
             let key = vault_encrypted.key(password);
             let vault = vault_encrypted.decrypt(key)?.deserialize();
@@ -220,9 +523,9 @@ impl VaultHandler {
             // schema,
             record,
             save_dir,
-            vault_file: Rc::new(RefCell::new(vault_file)),
-            record_file: Rc::new(RefCell::new(record_file)),
-            schema_file: Rc::new(RefCell::new(schema_file)),
+            vault_file,
+            record_file,
+            schema_file,
         })
     }
 
@@ -230,11 +533,13 @@ impl VaultHandler {
         if let Some(file) = self.save_dir.record_file_latest() {
             self.apply_unfinished(file)?
         }
+        self.finish_rekey()?;
 
         Ok(())
     }
 
     fn apply_unfinished(&mut self, record_file: RecordFile) -> anyhow::Result<()> {
+        self.save_dir.check_permissions(&record_file.path())?;
         let record = record_file
             .read()?
             .deserialize()
@@ -242,17 +547,163 @@ impl VaultHandler {
             .deserialize();
         self.vault.apply_record(record);
         self.save()?;
-        record_file.delete()?;
+        self.save_dir.delete_file(&record_file.path())?;
         Ok(())
     }
 
     fn save(&mut self) -> anyhow::Result<()> {
         self.vault_encrypted.update(&self.vault, self.key)?;
-        self.vault_file.borrow_mut().write(&self.vault_encrypted)?;
-        self.schema_file.borrow_mut().write(&self.vault.schema())?;
+        self.vault_file.write(&self.vault_encrypted)?;
+        let mut schema = self.vault.schema();
+        if !self.save_dir.publishes_fields() {
+            schema.unpublish();
+        }
+        if self.save_dir.hides_metadata() {
+            schema.hide_metadata();
+        }
+        self.schema_file.write(&schema)?;
         Ok(())
     }
 
+    /// pick up what other processes saved since the vault was decrypted, using the key from
+    /// then, a vault whose password was rotated in the meantime can't be reloaded
+    fn reload(&mut self) -> anyhow::Result<()> {
+        let vault_file = self.vault_file.clone();
+        if vault_file.exists() {
+            self.save_dir.check_permissions(&vault_file.path())?;
+            let vault_encrypted = vault_file.read()?.deserialize();
+            if vault_encrypted.salt != self.vault_encrypted.salt {
+                return Err(ClientError::SessionLocked.into());
+            }
+            self.vault = vault_encrypted.decrypt(self.key)?.deserialize();
+            self.vault_encrypted = vault_encrypted;
+        }
+        self.check_unfinished()
+    }
+
+    fn get(&mut self, key: String) -> anyhow::Result<Reads<Store>> {
+        self.transaction(Command::Read { key }.into())
+    }
+
+    /// replace the entry's value, only the fields that changed are validated so values stored
+    /// before their kind was known don't block the update
+    fn update(&mut self, key: String, mut value: Store) -> anyhow::Result<Reads<Store>> {
+        let before = self.read(&key).ok();
+        value.validate(&value.changed_from(before.as_ref()))?;
+        value.touch();
+        self.transaction(Command::Update { key, value }.into())
+    }
+
+    /// apply the changes to the entry's current value
+    fn change(&mut self, key: String, changes: Changes) -> anyhow::Result<Reads<Store>> {
+        let read = self.read(&key).map_err(|_| ClientError::ReadNothing)?;
+        let changed = changes.changed();
+        let mut value = read.update(changes);
+        value.validate(&changed)?;
+        value.touch();
+        self.transaction(Command::Update { key, value }.into())
+    }
+
+    /// move an entry to a name that isn't taken yet
+    fn rename(&mut self, from: String, to: String) -> anyhow::Result<()> {
+        let value = self.read(&from).map_err(|_| ClientError::ReadNothing)?;
+        let taken = self.vault.data.contains_key(&to);
+        self.transaction(rename_commands(&from, &to, value, taken)?)?;
+        self.rename_attachments(&from, &to)
+    }
+
+    fn delete(&mut self, key: String) -> anyhow::Result<()> {
+        self.transaction(Command::Delete { key: key.clone() }.into())?;
+        self.detach_entry(&key)
+    }
+
+    /// run the steps as one transaction, nothing is saved when any of them fails, returning what
+    /// the `Get` steps read
+    ///
+    /// The attachments of renamed and deleted entries are moved or removed once the entries are
+    /// saved, an interruption in between can leave them under the old name.
+    fn batch(&mut self, steps: Vec<BatchStep>) -> anyhow::Result<Reads<Store>> {
+        // values the steps so far left the entries they touched with
+        let mut pending: BTreeMap<String, Option<Store>> = BTreeMap::new();
+        let mut reads = Reads::new();
+        // attachments to move along as (entry, new name), a missing name drops them
+        let mut attachments: Vec<(String, Option<String>)> = vec![];
+        let mut commands = Commands::new();
+        for step in steps {
+            match step {
+                BatchStep::Get(key) => match self.pending_value(&pending, &key) {
+                    Some(value) => {
+                        reads.insert(key, value);
+                    }
+                    None => {
+                        reads.remove(&key);
+                    }
+                },
+                BatchStep::Update(key, mut value) => {
+                    let before = self.pending_value(&pending, &key);
+                    value.validate(&value.changed_from(before.as_ref()))?;
+                    value.touch();
+                    pending.insert(key.clone(), Some(value.clone()));
+                    commands.push(Command::Update { key, value });
+                }
+                BatchStep::Change(key, changes) => {
+                    let read = self
+                        .pending_value(&pending, &key)
+                        .ok_or(ClientError::ReadNothing)?;
+                    let changed = changes.changed();
+                    let mut value = read.update(changes);
+                    value.validate(&changed)?;
+                    value.touch();
+                    pending.insert(key.clone(), Some(value.clone()));
+                    commands.push(Command::Update { key, value });
+                }
+                BatchStep::Rename(from, to) => {
+                    let value = self
+                        .pending_value(&pending, &from)
+                        .ok_or(ClientError::ReadNothing)?;
+                    let taken = self.pending_value(&pending, &to).is_some();
+                    for command in rename_commands(&from, &to, value.clone(), taken)? {
+                        commands.push(command);
+                    }
+                    pending.insert(from.clone(), None);
+                    pending.insert(to.clone(), Some(value));
+                    attachments.push((from, Some(to)));
+                }
+                BatchStep::Delete(key) => {
+                    pending.insert(key.clone(), None);
+                    commands.push(Command::Delete { key: key.clone() });
+                    attachments.push((key, None));
+                }
+            }
+        }
+        self.transaction(commands)?;
+        for (key, to) in attachments {
+            match to {
+                Some(to) => self.rename_attachments(&key, &to)?,
+                None => self.detach_entry(&key)?,
+            }
+        }
+        Ok(reads)
+    }
+
+    /// the value of an entry after the steps of a batch so far
+    fn pending_value(&self, pending: &BTreeMap<String, Option<Store>>, key: &str) -> Option<Store> {
+        match pending.get(key) {
+            Some(value) => value.clone(),
+            None => self.vault.data.get(key).cloned(),
+        }
+    }
+
+    /// the current value of an entry
+    fn read(&mut self, key: &str) -> anyhow::Result<Store> {
+        let reads = self.transaction(Command::Read { key: key.into() }.into())?;
+        reads
+            .data
+            .get(key)
+            .cloned()
+            .ok_or(CommunicationError::NoEntry.into())
+    }
+
     fn backup(&self) -> anyhow::Result<BackupFile> {
         let mut backup_file = self.save_dir.backup_file();
         let backup = VaultEncrypted {
@@ -260,17 +711,632 @@ impl VaultHandler {
             data: Encrypted::encrypt(&self.vault, self.key)?,
         };
         backup_file.write(&backup)?;
+        // attachments are encrypted with the same key, so they stay readable with the backup
+        let attachments = self.save_dir.attachment_dir();
+        if attachments.exists() {
+            utils::copy_dir(
+                &attachments,
+                &self.save_dir.attachment_backup_dir(&backup_file),
+            )?;
+        }
         Ok(backup_file)
     }
 
+    /// swap in the attachments saved with a backup, a backup without any leaves none
+    fn restore_attachments(&self, backup_file: &BackupFile) -> anyhow::Result<()> {
+        let attachments = self.save_dir.attachment_dir();
+        if attachments.exists() {
+            self.save_dir.delete_dir(&attachments)?;
+        }
+        let backup_attachments = self.save_dir.attachment_backup_dir(backup_file);
+        if backup_attachments.exists() {
+            utils::copy_dir(&backup_attachments, &attachments)?;
+        }
+        Ok(())
+    }
+
+    fn attachment_index(&self) -> anyhow::Result<AttachmentIndex> {
+        let file = self.save_dir.attachment_index_file();
+        if !file.exists() {
+            return Ok(AttachmentIndex::default());
+        }
+        self.save_dir.check_permissions(&file.path())?;
+        Ok(file.read()?.deserialize().decrypt(self.key)?.deserialize())
+    }
+
+    fn save_attachment_index(&self, index: &AttachmentIndex) -> anyhow::Result<()> {
+        self.save_dir
+            .attachment_index_file()
+            .write(&Encrypted::encrypt(index, self.key)?)
+    }
+
+    fn read_attachment(&self, info: &AttachmentInfo) -> anyhow::Result<Attachment> {
+        let file = self.save_dir.attachment_file(&info.id);
+        self.save_dir.check_permissions(&file.path())?;
+        Ok(file.read()?.deserialize().decrypt(self.key)?.deserialize())
+    }
+
+    fn delete_attachment(&self, info: &AttachmentInfo) -> anyhow::Result<()> {
+        let path = self.save_dir.attachment_file(&info.id).path();
+        if path.exists() {
+            self.save_dir.delete_file(&path)?;
+        }
+        Ok(())
+    }
+
+    /// contents of every attachment of an entry
+    fn entry_attachments(
+        &self,
+        index: &AttachmentIndex,
+        key: &str,
+    ) -> anyhow::Result<BTreeMap<String, Attachment>> {
+        let mut attachments = BTreeMap::new();
+        for (name, info) in index.entry(key) {
+            attachments.insert(name, self.read_attachment(&info)?);
+        }
+        Ok(attachments)
+    }
+
+    /// add an attachment to an entry, replacing one with the same name
+    fn attach(
+        &self,
+        key: &str,
+        name: &str,
+        attachment: Attachment,
+        limits: AttachmentLimits,
+    ) -> anyhow::Result<()> {
+        if !self.vault.data.contains_key(key) {
+            return Err(CommunicationError::NoEntry.into());
+        }
+        let mut index = self.attachment_index()?;
+        let previous = index.remove(key, name);
+        limits.check(&index, attachment.size())?;
+
+        // the contents are written before the index refers to them, an interruption can only
+        // leave behind an unreferenced file
+        let info = AttachmentInfo::new(attachment.size());
+        self.save_dir
+            .attachment_file(&info.id)
+            .write(&Encrypted::encrypt(&attachment, self.key)?)?;
+        index.insert(key, name, info);
+        self.save_attachment_index(&index)?;
+        if let Some(previous) = previous {
+            self.delete_attachment(&previous)?;
+        }
+        Ok(())
+    }
+
+    fn detach(&self, key: &str, name: &str) -> anyhow::Result<()> {
+        let mut index = self.attachment_index()?;
+        let info = index
+            .remove(key, name)
+            .ok_or_else(|| AttachmentError::NoAttachment(name.into()))?;
+        self.save_attachment_index(&index)?;
+        self.delete_attachment(&info)
+    }
+
+    /// remove every attachment of an entry
+    fn detach_entry(&self, key: &str) -> anyhow::Result<()> {
+        let mut index = self.attachment_index()?;
+        let removed = index.remove_entry(key);
+        if removed.is_empty() {
+            return Ok(());
+        }
+        self.save_attachment_index(&index)?;
+        for info in removed.values() {
+            self.delete_attachment(info)?;
+        }
+        Ok(())
+    }
+
+    fn rename_attachments(&self, from: &str, to: &str) -> anyhow::Result<()> {
+        let mut index = self.attachment_index()?;
+        if index.data.contains_key(from) {
+            index.rename_entry(from, to);
+            self.save_attachment_index(&index)?;
+        }
+        Ok(())
+    }
+
+    /// encrypt the attachments under a new key into new files and a new index next to the current
+    /// one, the old ones stay in use until [Self::finish_rekey] once the vault is saved under the
+    /// new key
+    fn rekey_attachments(&self, key: Key<Aes256Gcm>) -> anyhow::Result<()> {
+        let index = self.attachment_index()?;
+        if index.data.is_empty() {
+            return Ok(());
+        }
+        let mut new_index = AttachmentIndex::default();
+        for (entry, attachments) in &index.data {
+            for (name, info) in attachments {
+                let attachment = self.read_attachment(info)?;
+                let new_info = AttachmentInfo::new(info.size);
+                self.save_dir
+                    .attachment_file(&new_info.id)
+                    .write(&Encrypted::encrypt(&attachment, key)?)?;
+                new_index.insert(entry, name, new_info);
+            }
+        }
+        self.save_dir
+            .rekeyed_index_file()
+            .write(&Encrypted::encrypt(&new_index, key)?)
+    }
+
+    /// settle an attachment rekey, the new index replaces the current one when the vault's key is
+    /// the one it was encrypted under, otherwise the vault wasn't saved and it's dropped, either
+    /// way the files only the unused index referred to are removed
+    fn finish_rekey(&self) -> anyhow::Result<()> {
+        let file = self.save_dir.rekeyed_index_file();
+        if !file.path().exists() {
+            return Ok(());
+        }
+        self.save_dir.check_permissions(&file.path())?;
+        if file.read()?.deserialize().decrypt(self.key).is_ok() {
+            std::fs::rename(file.path(), self.save_dir.attachment_index_file().path())?;
+        } else {
+            self.save_dir.delete_file(&file.path())?;
+        }
+        let index = self.attachment_index()?;
+        let used: BTreeSet<&str> = index.all().map(|info| info.id.as_str()).collect();
+        for id in self.save_dir.attachment_ids() {
+            if !used.contains(id.as_str()) {
+                self.save_dir
+                    .delete_file(&self.save_dir.attachment_file(&id).path())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn transfer(
+        &mut self,
+        destination: &mut Self,
+        limits: AttachmentLimits,
+        keys: Vec<String>,
+        keep: bool,
+    ) -> anyhow::Result<Vec<BackupFile>> {
+        let reads: Commands = keys
+            .iter()
+            .map(|key| Command::Read { key: key.clone() })
+            .collect::<Vec<_>>()
+            .into();
+        let source_reads = self.transaction(reads.clone())?;
+        let destination_reads = destination.transaction(reads)?;
+
+        let mut updates = Commands::new();
+        let mut deletes = Commands::new();
+        for key in keys.clone() {
+            let value = source_reads
+                .data
+                .get(&key)
+                .ok_or(CommunicationError::NoEntry)?;
+            if destination_reads.data.contains_key(&key) {
+                return Err(CommunicationError::ExistingEntry.into());
+            }
+            updates.push(Command::Update {
+                key: key.clone(),
+                value: value.clone(),
+            });
+            deletes.push(Command::Delete { key });
+        }
+
+        let backups = vec![self.backup()?, destination.backup()?];
+        // write the destination before removing from the source, an interruption can then only
+        // leave the entries in both vaults rather than in neither
+        destination.transaction(updates)?;
+        let index = self.attachment_index()?;
+        for key in &keys {
+            for (name, attachment) in self.entry_attachments(&index, key)? {
+                destination.attach(key, &name, attachment, limits)?;
+            }
+        }
+        if !keep {
+            self.transaction(deletes)?;
+            for key in &keys {
+                self.detach_entry(key)?;
+            }
+        }
+        Ok(backups)
+    }
+
     fn transaction(&mut self, commands: Commands) -> anyhow::Result<Reads<Store>> {
         let (reads, record) = self.vault.transaction(commands);
         self.record.update(&record, self.key)?;
 
-        self.record_file.borrow_mut().write(&self.record)?;
+        self.record_file.write(&self.record)?;
         self.vault.apply_record(record);
         self.save()?;
-        self.record_file.borrow_mut().delete()?;
+        let record_path = self.record_file.path();
+        self.save_dir.delete_file(&record_path)?;
         Ok(reads)
     }
 }
+
+/// the commands moving a value from one entry to another, failing when the other one is taken
+fn rename_commands(from: &str, to: &str, value: Store, taken: bool) -> anyhow::Result<Commands> {
+    if taken {
+        return Err(CommunicationError::ExistingEntry.into());
+    }
+    let delete = Command::Delete { key: from.into() };
+    let create = Command::Update {
+        key: to.into(),
+        value,
+    };
+    Ok(vec![delete, create].into())
+}
+
+impl Drop for VaultHandler {
+    fn drop(&mut self) {
+        // the entries' values zero themselves as secrets, the key has to be done by hand
+        self.key.as_mut_slice().zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use secrecy::{ExposeSecret, Secret};
+
+    use super::{VaultHandler, VaultInterface};
+    use crate::{
+        attachment::Attachment,
+        command::Command,
+        config::vault_config::VaultConfig,
+        errors::PermissionError,
+        file::{PermissionCheck, ProjectFile, SaveDir},
+        message::{BatchStep, Message},
+        output::Output,
+        reads::Reads,
+        schema::Schema,
+        secure::SecureData,
+        store::{Store, StoredValue},
+        vault::encrypted::VaultEncrypted,
+        Password,
+    };
+
+    fn password() -> Password {
+        Secret::new("password".into())
+    }
+
+    fn entry(value: &str) -> Store {
+        Store::password(StoredValue::new(value))
+    }
+
+    fn stored(store: &Store) -> String {
+        store.get("Password").unwrap().expose_secret().to_string()
+    }
+
+    fn vault() -> (tempfile::TempDir, VaultInterface) {
+        let dir = tempfile::tempdir().unwrap();
+        let interface = VaultInterface::new(dir.path().into());
+        interface
+            .receive(Message::Update(password(), "a".into(), entry("old")))
+            .unwrap();
+        (dir, interface)
+    }
+
+    /// a vault with an entry that has a username
+    fn with_username(config: VaultConfig) -> VaultInterface {
+        let interface = VaultInterface::with_config(config);
+        let store = Store::inferred(vec![
+            ("username".to_string(), StoredValue::new("alice").into()),
+            ("password".to_string(), StoredValue::new("secret").into()),
+        ]);
+        interface
+            .receive(Message::Update(password(), "a".into(), store))
+            .unwrap();
+        interface
+    }
+
+    fn schema(interface: &VaultInterface, message: Message) -> Schema {
+        match interface.receive(message).unwrap() {
+            Output::Schema(schema) => schema,
+            output => panic!("unexpected output {output:?}"),
+        }
+    }
+
+    fn batch(interface: &VaultInterface, steps: Vec<BatchStep>) -> anyhow::Result<Reads<Store>> {
+        match interface.receive(Message::Batch(password(), steps))? {
+            Output::Read(reads) => Ok(reads),
+            output => panic!("unexpected output {output:?}"),
+        }
+    }
+
+    #[test]
+    fn batch_get_sees_earlier_update() {
+        let (_dir, interface) = vault();
+        let reads = batch(
+            &interface,
+            vec![
+                BatchStep::Update("a".into(), entry("new")),
+                BatchStep::Get("a".into()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(stored(&reads.data["a"]), "new");
+    }
+
+    #[test]
+    fn batch_get_sees_earlier_delete() {
+        let (_dir, interface) = vault();
+        let reads = batch(
+            &interface,
+            vec![BatchStep::Delete("a".into()), BatchStep::Get("a".into())],
+        )
+        .unwrap();
+        assert!(!reads.data.contains_key("a"));
+    }
+
+    #[test]
+    fn batch_get_before_update_sees_old_value() {
+        let (_dir, interface) = vault();
+        let reads = batch(
+            &interface,
+            vec![
+                BatchStep::Get("a".into()),
+                BatchStep::Update("b".into(), entry("other")),
+            ],
+        )
+        .unwrap();
+        assert_eq!(stored(&reads.data["a"]), "old");
+        assert!(!reads.data.contains_key("b"));
+    }
+
+    #[test]
+    fn batch_rename_onto_existing_fails() {
+        let (_dir, interface) = vault();
+        let result = batch(
+            &interface,
+            vec![
+                BatchStep::Update("b".into(), entry("other")),
+                BatchStep::Rename("b".into(), "a".into()),
+            ],
+        );
+        assert!(result.is_err());
+        // nothing of the failed batch is saved
+        let reads = batch(
+            &interface,
+            vec![BatchStep::Get("a".into()), BatchStep::Get("b".into())],
+        )
+        .unwrap();
+        assert_eq!(stored(&reads.data["a"]), "old");
+        assert!(!reads.data.contains_key("b"));
+    }
+
+    #[test]
+    fn rename_onto_existing_fails() {
+        let (_dir, interface) = with_attachment();
+        interface
+            .receive(Message::Update(password(), "b".into(), entry("other")))
+            .unwrap();
+        let message = Message::Rename(password(), "a".into(), "b".into());
+        assert!(interface.receive(message).is_err());
+        let reads = batch(
+            &interface,
+            vec![BatchStep::Get("a".into()), BatchStep::Get("b".into())],
+        )
+        .unwrap();
+        assert_eq!(stored(&reads.data["a"]), "old");
+        assert_eq!(stored(&reads.data["b"]), "other");
+        assert_eq!(attachment(&interface, password()), b"contents");
+    }
+
+    #[test]
+    fn batch_rename_onto_deleted_entry() {
+        let (_dir, interface) = vault();
+        let reads = batch(
+            &interface,
+            vec![
+                BatchStep::Update("b".into(), entry("other")),
+                BatchStep::Delete("a".into()),
+                BatchStep::Rename("b".into(), "a".into()),
+                BatchStep::Get("a".into()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(stored(&reads.data["a"]), "other");
+    }
+
+    fn new_password() -> Password {
+        Secret::new("new password".into())
+    }
+
+    fn with_attachment() -> (tempfile::TempDir, VaultInterface) {
+        let (dir, interface) = vault();
+        let attachment = Attachment::new(b"contents".to_vec());
+        interface
+            .receive(Message::Attach(
+                password(),
+                "a".into(),
+                "file".into(),
+                attachment,
+            ))
+            .unwrap();
+        (dir, interface)
+    }
+
+    fn attachment(interface: &VaultInterface, password: Password) -> Vec<u8> {
+        let message = Message::GetAttachment(password, "a".into(), "file".into());
+        match interface.receive(message).unwrap() {
+            Output::Attachment(attachment) => attachment.expose_secret().to_vec(),
+            output => panic!("unexpected output {output:?}"),
+        }
+    }
+
+    #[test]
+    fn rotate_keeps_attachments() {
+        let (_dir, interface) = with_attachment();
+        interface
+            .receive(Message::Rotate(password(), new_password()))
+            .unwrap();
+        assert_eq!(attachment(&interface, new_password()), b"contents");
+        assert_eq!(interface.config.save_dir().attachment_ids().len(), 1);
+    }
+
+    #[test]
+    fn legacy_values_do_not_block_updates() {
+        let (dir, interface) = vault();
+        // stored before kinds existed, the expiry isn't a date its inferred kind accepts
+        let legacy = Store::new(vec![
+            ("Password".to_string(), StoredValue::new("old").into()),
+            ("Expiry".to_string(), StoredValue::new("someday").into()),
+        ]);
+        let mut handler =
+            VaultHandler::load_interface(password(), SaveDir::new(dir.path().into())).unwrap();
+        handler
+            .transaction(
+                Command::Update {
+                    key: "b".into(),
+                    value: legacy.clone(),
+                }
+                .into(),
+            )
+            .unwrap();
+        drop(handler);
+
+        let mut value = legacy.clone();
+        value.insert("Password", StoredValue::new("new").into());
+        interface
+            .receive(Message::Update(password(), "b".into(), value))
+            .unwrap();
+        let mut value = legacy;
+        value.insert("Expiry", StoredValue::new("later").into());
+        assert!(interface
+            .receive(Message::Update(password(), "b".into(), value))
+            .is_err());
+    }
+
+    #[test]
+    fn rekey_interrupted_before_save() {
+        let (_dir, interface) = with_attachment();
+        let save_dir = interface.config.save_dir();
+        let handler = VaultHandler::load_interface(password(), save_dir.clone()).unwrap();
+        let key = VaultEncrypted::new(new_password())
+            .unwrap()
+            .key(new_password());
+        handler.rekey_attachments(key).unwrap();
+        drop(handler);
+
+        assert_eq!(attachment(&interface, password()), b"contents");
+        assert!(!save_dir.rekeyed_index_file().path().exists());
+        assert_eq!(save_dir.attachment_ids().len(), 1);
+    }
+
+    #[test]
+    fn rekey_interrupted_after_save() {
+        let (_dir, interface) = with_attachment();
+        let save_dir = interface.config.save_dir();
+        let mut handler = VaultHandler::load_interface(password(), save_dir.clone()).unwrap();
+        let new_vault = VaultEncrypted::new(new_password()).unwrap();
+        let key = new_vault.key(new_password());
+        handler.rekey_attachments(key).unwrap();
+        handler.vault_encrypted = new_vault;
+        handler.key = key;
+        handler.save().unwrap();
+        drop(handler);
+
+        assert_eq!(attachment(&interface, new_password()), b"contents");
+        assert!(!save_dir.rekeyed_index_file().path().exists());
+        assert_eq!(save_dir.attachment_ids().len(), 1);
+    }
+
+    #[test]
+    fn session_locks_while_idle() {
+        let (_dir, interface) = vault();
+        let mut session = interface
+            .unlock(password(), Duration::from_millis(200))
+            .unwrap();
+        assert!(session.state.lock().unwrap().handler.is_some());
+        thread::sleep(Duration::from_millis(800));
+        // the key is gone without the session being used again
+        assert!(session.state.lock().unwrap().handler.is_none());
+        assert!(session.get("a").is_err());
+    }
+
+    #[test]
+    fn session_runs_operations() {
+        let (_dir, interface) = vault();
+        let mut session = interface
+            .unlock(password(), Duration::from_secs(60))
+            .unwrap();
+        session.update("b", entry("other")).unwrap();
+        assert_eq!(stored(&session.get("a").unwrap()), "old");
+        assert_eq!(stored(&session.get("b").unwrap()), "other");
+        session.lock();
+        assert!(session.is_locked());
+        assert!(session.get("a").is_err());
+    }
+
+    #[test]
+    fn private_fields_stay_encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = VaultConfig::new(dir.path().into()).public_fields(false);
+        let interface = with_username(config);
+        let public = schema(&interface, Message::Schema);
+        assert!(public.public.is_empty());
+        assert!(public.unpublished);
+        let metadata = schema(&interface, Message::Metadata(password()));
+        assert_eq!(metadata.public("a", "username"), Some("alice"));
+    }
+
+    #[test]
+    fn public_fields_are_in_the_schema_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let interface = with_username(VaultConfig::new(dir.path().into()));
+        let public = schema(&interface, Message::Schema);
+        assert_eq!(public.public("a", "username"), Some("alice"));
+        assert!(!public.unpublished);
+    }
+
+    #[test]
+    fn unpublished_values_are_stripped_from_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let interface = with_username(VaultConfig::new(dir.path().into()));
+        let path = SaveDir::new(dir.path().into()).schema_file().path();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("alice"));
+        schema(&interface, Message::Schema);
+        assert!(std::fs::read_to_string(&path).unwrap().contains("alice"));
+        interface.unpublish_fields().unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("alice"));
+        assert!(schema(&interface, Message::Schema).public.is_empty());
+    }
+
+    /// make the file writable by everyone and load the vault refusing such files
+    #[cfg(unix)]
+    fn shared(dir: &tempfile::TempDir, path: &std::path::Path) -> VaultInterface {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o666)).unwrap();
+        let config = VaultConfig::new(dir.path().into()).permissions(PermissionCheck::Refuse);
+        VaultInterface::with_config(config)
+    }
+
+    #[cfg(unix)]
+    fn refused(result: anyhow::Result<Output>) -> bool {
+        matches!(
+            result.unwrap_err().downcast_ref(),
+            Some(PermissionError::SharedWritable(_))
+        )
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shared_schema_file_is_refused() {
+        let (dir, _) = vault();
+        let path = SaveDir::new(dir.path().into()).schema_file().path();
+        let interface = shared(&dir, &path);
+        assert!(refused(interface.receive(Message::Schema)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shared_record_file_is_refused() {
+        let (dir, _) = vault();
+        let path = SaveDir::new(dir.path().into()).record_file().path();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "{}").unwrap();
+        let interface = shared(&dir, &path);
+        assert!(refused(
+            interface.receive(Message::Get(password(), "a".into()))
+        ));
+    }
+}
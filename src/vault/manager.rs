@@ -1,17 +1,29 @@
+use std::{
+    fs,
+    path::{Component, Path},
+    time::Duration,
+};
+
 use crate::{
+    audit::Auditor,
     config::{
         internal_config::{BaseConfig, InternalConfig},
         manager_config::ManagerConfig,
+        vault_config::VaultConfig,
     },
-    errors::ManagerError,
+    doctor::{self, Problem},
+    errors::{ClientError, ManagerError},
+    file::SaveDir,
     info::Info,
     manager_message::ManagerMessage,
     message::Message,
     output::Output,
-    utils,
+    policy::AgePolicy,
+    strength::StrengthPolicy,
+    utils, Password,
 };
 
-use super::interface::VaultInterface;
+use super::interface::{VaultInterface, VaultSession};
 
 pub struct VaultManager {
     config: ManagerConfig,
@@ -34,23 +46,23 @@ impl VaultManager {
         match message {
             ManagerMessage::Empty => Ok(().into()),
             ManagerMessage::NewVault(name) => {
-                if let std::collections::btree_map::Entry::Vacant(e) =
-                    self.config.map.entry(name.clone())
-                {
+                check_name(&name)?;
+                if self.config.is_taken(&name) {
+                    Err(ManagerError::VaultExists.into())
+                } else {
                     let mut path = utils::base_path();
                     path.push(name.clone());
-                    e.insert(path.to_str().unwrap().into());
+                    self.config.map.insert(name, path.to_str().unwrap().into());
                     self.config.save()?;
                     Ok(().into())
-                } else {
-                    Err(ManagerError::VaultExists.into())
                 }
             }
             ManagerMessage::DeleteVault(name, password) => {
+                let name = self.config.resolve(&name);
                 if let Some(path) = self.config.map.get(&name) {
-                    let interface = VaultInterface::new(path.to_path_buf());
+                    let interface = self.interface(path);
                     interface.delete(password)?;
-                    self.config.map.remove(&name);
+                    self.config.forget_vault(&name);
                     self.config.save()?;
                     Ok(().into())
                 } else {
@@ -58,24 +70,102 @@ impl VaultManager {
                 }
             }
             ManagerMessage::DeleteEmptyVault(name) => {
+                let name = self.config.resolve(&name);
                 if let Some(path) = self.config.map.get(&name) {
-                    let interface = VaultInterface::new(path.to_path_buf());
+                    let interface = self.interface(path);
                     interface.delete_empty()?;
-                    self.config.map.remove(&name);
+                    self.config.forget_vault(&name);
+                    self.config.save()?;
+                    Ok(().into())
+                } else {
+                    Err(ManagerError::VaultDoesNotExist.into())
+                }
+            }
+            ManagerMessage::RenameVault(from, to) => self.rename(from, to),
+            ManagerMessage::AddAlias(alias, name) => {
+                let name = self.config.resolve(&name);
+                if self.config.is_taken(&alias) {
+                    Err(ManagerError::VaultExists.into())
+                } else if !self.config.map.contains_key(&name) {
+                    Err(ManagerError::VaultDoesNotExist.into())
+                } else {
+                    self.config.aliases.insert(alias, name);
+                    self.config.save()?;
+                    Ok(().into())
+                }
+            }
+            ManagerMessage::RemoveAlias(alias) => {
+                if self.config.aliases.remove(&alias).is_some() {
+                    self.config.save()?;
+                    Ok(().into())
+                } else {
+                    Err(ManagerError::AliasDoesNotExist.into())
+                }
+            }
+            ManagerMessage::SetDefaultVault(name) => {
+                let name = self.config.resolve(&name);
+                if self.config.map.contains_key(&name) {
+                    self.config.default_vault = name;
                     self.config.save()?;
                     Ok(().into())
                 } else {
                     Err(ManagerError::VaultDoesNotExist.into())
                 }
             }
+            ManagerMessage::HideMetadata(name, hide) => {
+                let name = self.config.resolve(&name);
+                let path = self
+                    .config
+                    .map
+                    .get(&name)
+                    .ok_or(ManagerError::VaultDoesNotExist)?
+                    .clone();
+                if hide {
+                    self.config.hidden_metadata.insert(name);
+                    self.interface(&path).hide_metadata()?;
+                } else {
+                    self.config.hidden_metadata.remove(&name);
+                }
+                self.config.save()?;
+                Ok(().into())
+            }
+            ManagerMessage::PublishFields(name, publish) => {
+                let name = self.config.resolve(&name);
+                let path = self
+                    .config
+                    .map
+                    .get(&name)
+                    .ok_or(ManagerError::VaultDoesNotExist)?
+                    .clone();
+                if publish {
+                    self.config.private_fields.remove(&name);
+                } else {
+                    self.config.private_fields.insert(name);
+                    self.interface(&path).unpublish_fields()?;
+                }
+                self.config.save()?;
+                Ok(().into())
+            }
+            ManagerMessage::Doctor => Ok(self.doctor().into()),
+            ManagerMessage::Repair(problem, password) => {
+                self.repair(problem, password)?;
+                Ok(().into())
+            }
             ManagerMessage::VaultMessage(name, message) => {
+                let name = self.config.resolve(&name);
                 if let Some(path) = self.config.map.get(&name) {
-                    let interface = VaultInterface::new(path.to_path_buf());
+                    let interface = self.interface(path);
                     interface.receive(message)
                 } else {
                     Err(ManagerError::VaultDoesNotExist.into())
                 }
             }
+            ManagerMessage::MoveEntries(from, from_password, to, to_password, keys) => {
+                self.transfer(from, from_password, to, to_password, keys, false)
+            }
+            ManagerMessage::CopyEntries(from, from_password, to, to_password, keys) => {
+                self.transfer(from, from_password, to, to_password, keys, true)
+            }
             ManagerMessage::List => Ok(self
                 .config
                 .map
@@ -83,10 +173,23 @@ impl VaultManager {
                 .into_keys()
                 .collect::<Vec<_>>()
                 .into()),
+            ManagerMessage::Audit(vaults, settings) => {
+                let mut auditor = Auditor::new(settings)?;
+                for (name, password) in vaults {
+                    let name = self.config.resolve(&name);
+                    let path = self
+                        .config
+                        .map
+                        .get(&name)
+                        .ok_or(ManagerError::VaultDoesNotExist)?;
+                    self.interface(path).audit(password, &name, &mut auditor)?;
+                }
+                Ok(auditor.finish().into())
+            }
             ManagerMessage::Info => {
                 let mut info = Info::default();
                 for (name, path) in &self.config.map {
-                    let interface = VaultInterface::new(path.to_path_buf());
+                    let interface = self.interface(path);
                     if let Ok(Output::Schema(schema)) = interface.receive(Message::Schema) {
                         info.insert(name.to_string(), schema);
                     }
@@ -95,4 +198,268 @@ impl VaultManager {
             }
         }
     }
+
+    fn interface(&self, path: &Path) -> VaultInterface {
+        let config = VaultConfig::new(path.to_path_buf())
+            .permissions(self.config.permissions)
+            .secure_delete(self.config.secure_delete)
+            .attachment_limits(self.config.attachment_limits)
+            .hide_metadata(self.is_hidden(path))
+            .public_fields(!self.is_private(path))
+            .age_policy(self.age_policy(path))
+            .strength_policy(self.strength_policy(path));
+        VaultInterface::with_config(config)
+    }
+
+    /// whether the vault at the path hides its metadata
+    fn is_hidden(&self, path: &Path) -> bool {
+        self.name_of(path)
+            .is_some_and(|name| self.config.hidden_metadata.contains(name))
+    }
+
+    /// whether the vault at the path keeps its public fields encrypted
+    fn is_private(&self, path: &Path) -> bool {
+        self.name_of(path)
+            .is_some_and(|name| self.config.private_fields.contains(name))
+    }
+
+    /// the age policy of the vault at the path
+    fn age_policy(&self, path: &Path) -> AgePolicy {
+        self.name_of(path)
+            .and_then(|name| self.config.age_policies.get(name))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// the strength policy of the vault at the path
+    fn strength_policy(&self, path: &Path) -> StrengthPolicy {
+        self.name_of(path)
+            .and_then(|name| self.config.strength_policies.get(name))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// name of the vault at the path
+    fn name_of(&self, path: &Path) -> Option<&String> {
+        self.config
+            .map
+            .iter()
+            .find(|(_, vault)| *vault == path)
+            .map(|(name, _)| name)
+    }
+
+    /// unlock the vault for a session of operations that don't need the password again, see
+    /// [VaultSession]
+    pub fn unlock(
+        &self,
+        name: &str,
+        password: Password,
+        timeout: Duration,
+    ) -> anyhow::Result<VaultSession> {
+        let name = self.config.resolve(name);
+        let path = self
+            .config
+            .map
+            .get(&name)
+            .ok_or(ManagerError::VaultDoesNotExist)?;
+        self.interface(path).unlock(password, timeout)
+    }
+
+    /// the vault a client means when it doesn't name one
+    pub fn default_vault(&self) -> String {
+        self.config.default_vault.clone()
+    }
+
+    /// the vault name an alias refers to, or the name itself when it isn't an alias
+    pub fn resolve(&self, name: &str) -> String {
+        self.config.resolve(name)
+    }
+
+    /// check the registered vaults and the files on disk for problems
+    pub fn doctor(&self) -> Vec<Problem> {
+        doctor::check(&self.config)
+    }
+
+    /// apply the fix described by [Problem::fix]
+    pub fn repair(&mut self, problem: Problem, password: Option<Password>) -> anyhow::Result<()> {
+        match problem {
+            Problem::MissingDirectory { vault, .. } => {
+                self.config.forget_vault(&vault);
+                self.config.save()?;
+            }
+            Problem::UnregisteredVault { name, path } => {
+                if self.config.is_taken(&name) {
+                    return Err(ManagerError::VaultExists.into());
+                }
+                self.config.map.insert(name, path);
+                self.config.save()?;
+            }
+            Problem::InterruptedTransaction { vault, .. } => {
+                let path = self
+                    .config
+                    .map
+                    .get(&vault)
+                    .ok_or(ManagerError::VaultDoesNotExist)?;
+                let password = password.ok_or(ClientError::PasswordRequired)?;
+                self.interface(path).replay(password)?;
+            }
+            Problem::MalformedTimestamp { vault, path } => {
+                let vault_path = self
+                    .config
+                    .map
+                    .get(&vault)
+                    .ok_or(ManagerError::VaultDoesNotExist)?;
+                SaveDir::new(vault_path.to_path_buf()).quarantine(&path)?;
+            }
+            Problem::Permissions { path, .. } => {
+                // an earlier fix may have already moved the file
+                if path.exists() {
+                    doctor::restrict(&path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn rename(&mut self, from: String, to: String) -> anyhow::Result<Output> {
+        let from = self.config.resolve(&from);
+        check_name(&to)?;
+        if self.config.is_taken(&to) {
+            return Err(ManagerError::VaultExists.into());
+        }
+        let path = self
+            .config
+            .map
+            .get(&from)
+            .cloned()
+            .ok_or(ManagerError::VaultDoesNotExist)?;
+
+        // only move directories that pants named itself, custom locations are left alone
+        let mut default_path = utils::base_path();
+        default_path.push(&from);
+        let path = if path == default_path {
+            let mut new_path = utils::base_path();
+            new_path.push(&to);
+            if path.exists() {
+                fs::rename(&path, &new_path)?;
+            }
+            new_path
+        } else {
+            path
+        };
+        // the directory is moved by now, so the vault can be forgotten under its old name
+        self.config.map.remove(&from);
+
+        for vault in self.config.aliases.values_mut() {
+            if *vault == from {
+                vault.clone_from(&to);
+            }
+        }
+        if self.config.default_vault == from {
+            self.config.default_vault.clone_from(&to);
+        }
+        if self.config.hidden_metadata.remove(&from) {
+            self.config.hidden_metadata.insert(to.clone());
+        }
+        if self.config.private_fields.remove(&from) {
+            self.config.private_fields.insert(to.clone());
+        }
+        if let Some(policy) = self.config.age_policies.remove(&from) {
+            self.config.age_policies.insert(to.clone(), policy);
+        }
+        if let Some(policy) = self.config.strength_policies.remove(&from) {
+            self.config.strength_policies.insert(to.clone(), policy);
+        }
+        self.config.map.insert(to, path);
+        self.config.save()?;
+        Ok(().into())
+    }
+
+    fn transfer(
+        &self,
+        from: String,
+        from_password: Password,
+        to: String,
+        to_password: Password,
+        keys: Vec<String>,
+        keep: bool,
+    ) -> anyhow::Result<Output> {
+        let from = self.config.resolve(&from);
+        let to = self.config.resolve(&to);
+        if from == to {
+            return Err(ManagerError::SameVault.into());
+        }
+        match (self.config.map.get(&from), self.config.map.get(&to)) {
+            (Some(from_path), Some(to_path)) => {
+                let source = self.interface(from_path);
+                let destination = self.interface(to_path);
+                let backups =
+                    source.transfer(from_password, &destination, to_password, keys, keep)?;
+                Ok(backups.into())
+            }
+            _ => Err(ManagerError::VaultDoesNotExist.into()),
+        }
+    }
+}
+
+/// vault names become directory names, so they can't point anywhere else
+fn check_name(name: &str) -> Result<(), ManagerError> {
+    let path = Path::new(name);
+    let mut components = path.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) if !name.contains(['/', '\\']) => Ok(()),
+        _ => Err(ManagerError::InvalidName),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_name, VaultManager};
+    use crate::{
+        config::manager_config::ManagerConfig,
+        doctor::Problem,
+        errors::{ClientError, ManagerError},
+        manager_message::ManagerMessage,
+    };
+
+    #[test]
+    fn replay_without_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = ManagerConfig::default();
+        config.map.insert("work".into(), dir.path().into());
+        let mut manager = VaultManager { config };
+        let problem = Problem::InterruptedTransaction {
+            vault: "work".into(),
+            path: dir.path().join("record"),
+        };
+        let error = manager.repair(problem, None).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(ClientError::PasswordRequired)
+        ));
+    }
+
+    #[test]
+    fn vault_names_are_not_paths() {
+        assert!(check_name("work").is_ok());
+        assert!(check_name("my.vault").is_ok());
+        for name in ["", ".", "..", "../work", "a/b", "a\\b", "/tmp/work"] {
+            assert!(check_name(name).is_err(), "{name:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn rename_to_a_path_leaves_the_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = ManagerConfig::default();
+        config.map.insert("work".into(), dir.path().into());
+        let mut manager = VaultManager { config };
+        let message = ManagerMessage::RenameVault("work".into(), "../work".into());
+        let error = manager.receive(message).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(ManagerError::InvalidName)
+        ));
+        assert_eq!(manager.config.map["work"], dir.path());
+    }
 }
@@ -5,14 +5,17 @@ pub mod manager;
 use core::str;
 use std::collections::BTreeMap;
 
+use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     action::{Action, Record},
     command::Commands,
+    migration,
     operation::{Operation, Operations},
     reads::Reads,
     schema::Schema,
+    secure::Versioned,
     store::Store,
 };
 
@@ -21,6 +24,16 @@ pub struct Vault {
     data: BTreeMap<String, Store>,
 }
 
+impl From<BTreeMap<String, Store>> for Vault {
+    fn from(data: BTreeMap<String, Store>) -> Self {
+        Self { data }
+    }
+}
+
+impl Versioned for Vault {
+    type Legacy = migration::v1::Vault;
+}
+
 impl Default for Vault {
     fn default() -> Self {
         Self::new()
@@ -90,6 +103,10 @@ impl Vault {
         }
     }
 
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &Store)> {
+        self.data.iter()
+    }
+
     pub fn keys(self) -> Vec<String> {
         self.data.into_keys().collect()
     }
@@ -101,11 +118,66 @@ impl Vault {
                 key.to_string(),
                 value.data.iter().map(|(x, _)| x.to_string()).collect(),
             );
+            schema.insert_kinds(
+                key.to_string(),
+                value
+                    .data
+                    .iter()
+                    .map(|(x, _)| (x.to_string(), value.kind(x)))
+                    .collect(),
+            );
+            schema.insert_specs(key.to_string(), value.specs.clone());
+            schema.insert_public(
+                key.to_string(),
+                value
+                    .data
+                    .iter()
+                    .filter(|(x, _)| value.kind(x).is_public())
+                    .map(|(x, v)| (x.to_string(), v.expose_secret().to_string()))
+                    .collect(),
+            );
+            schema.insert_tags(key.to_string(), value.tags.clone());
+            schema.insert_folder(key.to_string(), value.folder.clone());
+            schema.insert_modified(key.to_string(), value.modified);
         }
         schema
     }
 
-    pub fn export(&self) -> Result<String, serde_json::Error> {
-        serde_json::to_string(&self.data)
+    /// entries with fields whose value contains `query`, ignoring case, as a [Schema] listing
+    /// only the matching fields. Secret fields are skipped unless `include_secrets` is set
+    pub fn search(&self, query: &str, include_secrets: bool) -> Schema {
+        let query = query.to_lowercase();
+        let mut schema = Schema::new();
+        for (key, value) in &self.data {
+            let fields: Vec<&String> = value
+                .data
+                .iter()
+                .filter(|(field, _)| include_secrets || !value.kind(field).is_secret())
+                .filter(|(_, v)| {
+                    v.expose_secret()
+                        .to_string()
+                        .to_lowercase()
+                        .contains(&query)
+                })
+                .map(|(field, _)| field)
+                .collect();
+            if fields.is_empty() {
+                continue;
+            }
+            schema.insert(
+                key.to_string(),
+                fields.iter().map(|field| field.to_string()).collect(),
+            );
+            schema.insert_kinds(
+                key.to_string(),
+                fields
+                    .iter()
+                    .map(|field| (field.to_string(), value.kind(field)))
+                    .collect(),
+            );
+            schema.insert_tags(key.to_string(), value.tags.clone());
+            schema.insert_folder(key.to_string(), value.folder.clone());
+        }
+        schema
     }
 }